@@ -1,5 +1,106 @@
 pub mod book;
 pub mod chapter;
+pub mod format;
+pub mod metadata;
+pub mod note;
 pub mod paragraph;
 pub mod passage;
+pub mod render;
+pub mod span;
 pub mod verse;
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use self::book::{canonical_position, title_from_id, Book};
+use self::metadata::BibleMetadata;
+
+///A whole translation of the Bible with every book in canonical order
+#[derive(Serialize, Deserialize)]
+pub struct Bible {
+    pub metadata: BibleMetadata,
+    pub books: Vec<Book>,
+    ///where each book is in `books` by its title
+    titles: HashMap<String, usize>,
+}
+
+impl Bible {
+    ///parse every book out of the document in one go
+    pub fn new(bible_doc: &roxmltree::Document) -> anyhow::Result<Self> {
+        let mut books: Vec<(u32, Book)> = Vec::new();
+        for node in bible_doc.root_element().children() {
+            if !Book::is_book_tag(&node) {
+                continue;
+            }
+            //front matter, glossaries and the like aren't books we know how to read
+            let title = match node.attribute("id").and_then(title_from_id) {
+                Some(title) => title,
+                None => continue,
+            };
+            let position = canonical_position(title).context("book has no place in the canon")?;
+            books.push((position, Book::from_node(title.to_string(), node)?));
+        }
+        books.sort_by_key(|(position, _)| *position);
+
+        Ok(Bible::from_books(
+            BibleMetadata::from_usfx(bible_doc),
+            books.into_iter().map(|(_, book)| book).collect(),
+        ))
+    }
+
+    pub fn from_books(metadata: BibleMetadata, books: Vec<Book>) -> Self {
+        let titles = books
+            .iter()
+            .enumerate()
+            .map(|(i, book)| (book.title.to_string(), i))
+            .collect();
+        Self {
+            metadata,
+            books,
+            titles,
+        }
+    }
+
+    pub fn get(&self, title: &str) -> Option<&Book> {
+        self.titles.get(title).map(|&i| &self.books[i])
+    }
+
+    ///like `get` but with an error that's fit to show the user
+    pub fn book(&self, title: &str) -> anyhow::Result<&Book> {
+        if canonical_position(title).is_none() {
+            anyhow::bail!("{} is not a book of the Bible", title);
+        }
+        self.get(title)
+            .context(format!("cannot find the book of {}", title))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USFX: &str = r#"<usfx><languageCode>eng</languageCode><book id="FRT"><p>front matter</p></book><book id="JHN"><c id="1"/><p><v id="1"/>In the beginning was the Word.</p></book><book id="GEN"><c id="1"/><p><v id="1"/>In the beginning God created the heavens and the earth.</p></book></usfx>"#;
+
+    #[test]
+    fn bible_in_canonical_order() {
+        let doc = roxmltree::Document::parse(USFX).unwrap();
+        let bible = Bible::new(&doc).unwrap();
+
+        let titles: Vec<&str> = bible.books.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, vec!["Genesis", "John"]);
+        assert_eq!(bible.metadata.language, Some("eng".to_string()));
+    }
+
+    #[test]
+    fn bible_lookup() {
+        let doc = roxmltree::Document::parse(USFX).unwrap();
+        let bible = Bible::new(&doc).unwrap();
+
+        assert_eq!(bible.get("John").unwrap().id, "JHN");
+        assert!(bible.get("Mark").is_none());
+        assert!(bible.book("Mark").is_err());
+        assert!(bible.book("Hezekiah").is_err());
+    }
+}
@@ -5,11 +5,16 @@ use std::vec::Vec;
 use anyhow::Context;
 use once_cell::sync::Lazy;
 use roxmltree::Node;
+use serde::{Deserialize, Serialize};
 
 use super::chapter::Chapter;
 
-use super::paragraph::Paragraph;
-use super::verse::Verse;
+use super::note::{Note, NoteKind};
+use super::paragraph::{BlockKind, Paragraph};
+use super::passage::Passage;
+use super::span::Style;
+use super::verse::{Verse, VerseId};
+use crate::Query;
 
 const CHPT_NUM_TAG: &str = "c";
 const ALT_CHPT_NUM_TAG_1: &str = "ca";
@@ -17,13 +22,42 @@ const ALT_CHPT_NUM_TAG_2: &str = "cp";
 const BOOK_TAG: &str = "book";
 const P_PARA_TAG: &str = "p";
 const Q_PARA_TAG: &str = "q";
+const LIST_ITEM_TAG: &str = "li";
+const BLANK_LINE_TAG: &str = "b";
+// <q> and <li> use this for their indentation
+const LEVEL_ATTR: &str = "level";
+// <p> can carry the original USFM marker (ie. sfm="q2") when USFX has no element for it
+const SFM_ATTR: &str = "sfm";
 const ID_TAG: &str = "id";
 // const NEW_LN: &str = "\n";
 const VERSE_TAG: &str = "v";
-//can contain <nd> tags
-const WORD_TAG: &str = "w";
 // this tag can contain <w> or can be contained with <w>
 const NAME_DEITY_TAG: &str = "nd";
+const WORDS_OF_JESUS_TAG: &str = "wj";
+const ADDED_TAG: &str = "add";
+const ITALIC_TAG: &str = "it";
+const FOOTNOTE_TAG: &str = "f";
+const CROSS_REF_TAG: &str = "x";
+// the verse reference at the start of a note (ie. "3:16 ") which we don't want in the note text
+const FOOTNOTE_ORIGIN_TAG: &str = "fr";
+const CROSS_REF_ORIGIN_TAG: &str = "xo";
+const CALLER_ATTR: &str = "caller";
+const SECTION_TAG: &str = "s";
+
+///tags that are in among the verses but aren't scripture (notes are picked up separately)
+const NON_SCRIPTURE_TAGS: &[&str] = &[
+    FOOTNOTE_TAG,
+    CROSS_REF_TAG,
+    "fe",
+    "fig",
+    "rem",
+    "ca",
+    "va",
+    "vp",
+    "cl",
+    "ve",
+    "optionalLineBreak",
+];
 
 pub static BOOK_ORDER: Lazy<HashMap<u32, String>> = Lazy::new(|| {
     let mut map = HashMap::new();
@@ -75,7 +109,7 @@ pub static BOOK_ORDER: Lazy<HashMap<u32, String>> = Lazy::new(|| {
     map.insert(45, "Romans".to_string());
     map.insert(46, "1 Corinthians".to_string());
     map.insert(47, "2 Corinthians".to_string());
-    map.insert(48, "Galations".to_string());
+    map.insert(48, "Galatians".to_string());
     map.insert(49, "Ephesians".to_string());
     map.insert(50, "Philippians".to_string());
     map.insert(51, "Colossians".to_string());
@@ -147,7 +181,7 @@ pub static BOOK_TITLE_ID: Lazy<HashMap<String, String>> = Lazy::new(|| {
     map.insert("Romans".to_string(), "ROM".to_string());
     map.insert("1 Corinthians".to_string(), "1CO".to_string());
     map.insert("2 Corinthians".to_string(), "2CO".to_string());
-    map.insert("Galations".to_string(), "GAL".to_string());
+    map.insert("Galatians".to_string(), "GAL".to_string());
     map.insert("Ephesians".to_string(), "EPH".to_string());
     map.insert("Philippians".to_string(), "PHP".to_string());
     map.insert("Colossians".to_string(), "COL".to_string());
@@ -169,8 +203,27 @@ pub static BOOK_TITLE_ID: Lazy<HashMap<String, String>> = Lazy::new(|| {
     map
 });
 
+///the title of a book from its three letter id (ie. JHN -> John)
+pub fn title_from_id(id: &str) -> Option<&'static str> {
+    BOOK_TITLE_ID
+        .iter()
+        .find(|(_, book_id)| book_id.as_str() == id)
+        .map(|(title, _)| title.as_str())
+}
+
+///where the book is in the Protestant canon starting from 1 for Genesis
+pub fn canonical_position(title: &str) -> Option<u32> {
+    BOOK_ORDER
+        .iter()
+        .find(|(_, book_title)| book_title.as_str() == title)
+        .map(|(pos, _)| *pos)
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Book {
     pub title: String,
+    ///the three letter id used in the source text (ie. JHN)
+    pub id: String,
     pub chapters: Vec<Chapter>,
 }
 
@@ -178,14 +231,24 @@ impl Book {
     pub fn new(title: String, bible_doc: &roxmltree::Document) -> anyhow::Result<Self> {
         let title_id = BOOK_TITLE_ID
             .get(&title)
-            .context(format!("{} is not book of the Bible", &title))?;
+            .context(format!("{} is not a book of the Bible", &title))?;
         let full_book_node: Node = bible_doc
             .descendants()
-            .find(|node| node.has_tag_name(BOOK_TAG) && node.attribute(ID_TAG) == Some(title_id))
+            .find(|node| Book::is_book_tag(node) && node.attribute(ID_TAG) == Some(title_id))
             .context(format!("cannot find the book of {}", &title))?;
 
+        Book::from_node(title, full_book_node)
+    }
+
+    ///make a book out of its <book> node
+    pub fn from_node(title: String, full_book_node: Node) -> anyhow::Result<Self> {
+        let id = full_book_node
+            .attribute(ID_TAG)
+            .context(format!("the book of {} has no id", &title))?
+            .to_string();
         let mut book_struct = Book {
             title,
+            id,
             chapters: Vec::new(),
         };
         book_struct.make_chapters(full_book_node)?;
@@ -193,8 +256,26 @@ impl Book {
         Ok(book_struct)
     }
 
-    fn is_word_tag_or_text(v: &Node) -> bool {
-        v.has_tag_name(WORD_TAG) || v.is_text()
+    ///the chapter with this number
+    pub fn chapter(&self, number: u32) -> Option<&Chapter> {
+        self.chapters.iter().find(|c| c.number == number)
+    }
+
+    ///Cut exactly the verses of a query out of the book, keeping the paragraphs they're in
+    pub fn passage(&self, query: &Query) -> Passage {
+        let (start, end) = (query.entry_point, query.end_point);
+        let chapters = self
+            .chapters
+            .iter()
+            .filter(|ch| ch.number >= start.chpt && ch.number <= end.chpt)
+            .map(|ch| ch.slice(start, end))
+            .filter(|ch| !ch.paragraphs.is_empty())
+            .collect();
+        Passage { chapters }
+    }
+
+    pub fn is_book_tag(node: &Node) -> bool {
+        node.has_tag_name(BOOK_TAG) && node.has_attribute(ID_TAG)
     }
 
     fn is_verse_tag(v: &Node) -> bool {
@@ -208,7 +289,49 @@ impl Book {
     }
 
     fn is_paragraph_tag(child: &Node) -> bool {
-        child.has_tag_name(P_PARA_TAG) || child.has_tag_name(Q_PARA_TAG)
+        child.has_tag_name(P_PARA_TAG)
+            || child.has_tag_name(Q_PARA_TAG)
+            || child.has_tag_name(LIST_ITEM_TAG)
+            || child.has_tag_name(BLANK_LINE_TAG)
+    }
+
+    ///the level of a marker like `q2` or `li1`, no number means level 1
+    pub(crate) fn marker_level(marker: &str, prefix: &str) -> Option<u8> {
+        let level = marker.strip_prefix(prefix)?;
+        if level.is_empty() {
+            Some(1)
+        } else {
+            level.parse::<u8>().ok()
+        }
+    }
+
+    fn block_kind(child: &Node) -> BlockKind {
+        let level = child
+            .attribute(LEVEL_ATTR)
+            .and_then(|l| l.parse::<u8>().ok())
+            .unwrap_or(1);
+
+        if child.has_tag_name(Q_PARA_TAG) {
+            return BlockKind::Poetry(level);
+        } else if child.has_tag_name(LIST_ITEM_TAG) {
+            return BlockKind::ListItem(level);
+        } else if child.has_tag_name(BLANK_LINE_TAG) {
+            return BlockKind::Blank;
+        }
+
+        match child.attribute(SFM_ATTR) {
+            Some("b") => BlockKind::Blank,
+            Some(sfm) => {
+                if let Some(level) = Book::marker_level(sfm, Q_PARA_TAG) {
+                    BlockKind::Poetry(level)
+                } else if let Some(level) = Book::marker_level(sfm, LIST_ITEM_TAG) {
+                    BlockKind::ListItem(level)
+                } else {
+                    BlockKind::Prose
+                }
+            }
+            None => BlockKind::Prose,
+        }
     }
 
     fn init_chapter(child: &Node) -> anyhow::Result<Chapter> {
@@ -224,22 +347,124 @@ impl Book {
         })
     }
 
-    fn is_nd_tag(child: &Node) -> bool {
-        child.has_tag_name(NAME_DEITY_TAG)
+    ///the style inside of a character tag like <wj> or <nd> given the style outside of it
+    fn char_style(child: &Node, outer: Style) -> Style {
+        if child.has_tag_name(NAME_DEITY_TAG) {
+            Style {
+                divine_name: true,
+                ..outer
+            }
+        } else if child.has_tag_name(WORDS_OF_JESUS_TAG) {
+            Style {
+                words_of_jesus: true,
+                ..outer
+            }
+        } else if child.has_tag_name(ADDED_TAG) || child.has_tag_name(ITALIC_TAG) {
+            Style {
+                added: true,
+                ..outer
+            }
+        } else {
+            outer
+        }
+    }
+
+    ///Everything but the tags that aren't scripture is walked into however deeply it's nested,
+    ///tags we don't know about included, showing an odd word is better than silently losing one
+    fn is_scripture_tag(child: &Node) -> bool {
+        !NON_SCRIPTURE_TAGS.contains(&child.tag_name().name())
+    }
+
+    fn is_note_tag(child: &Node) -> bool {
+        child.has_tag_name(FOOTNOTE_TAG) || child.has_tag_name(CROSS_REF_TAG)
+    }
+
+    fn is_note_origin(node: &Node) -> bool {
+        node.has_tag_name(FOOTNOTE_ORIGIN_TAG) || node.has_tag_name(CROSS_REF_ORIGIN_TAG)
+    }
+
+    fn make_note(v: &Node, offset: usize) -> Note {
+        let kind = if v.has_tag_name(CROSS_REF_TAG) {
+            NoteKind::CrossReference
+        } else {
+            NoteKind::Footnote
+        };
+        let raw: String = v
+            .descendants()
+            .filter(|n| n.is_text() && !n.ancestors().any(|a| Book::is_note_origin(&a)))
+            .filter_map(|n| n.text())
+            .collect();
+        let text = raw.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let caller = v.attribute(CALLER_ATTR).unwrap_or_default();
+        Note::new(caller, kind, &text, offset)
     }
 
-    fn add_content_to_vs(&mut self, v: &Node, pgh: &mut Paragraph) {
-        //find the most recent verse
-        let verse_opt = pgh.verses.last_mut();
-        if let Some(most_recent_verse) = verse_opt {
-            //start adding contents to the verse
-            if let Some(t) = v.text() {
-                most_recent_verse.contents.push_str(&t.replace('\n', " "));
+    fn add_note_to_vs(&mut self, v: &Node, pgh: &mut Paragraph) {
+        if let Some(most_recent_verse) = pgh.verses.last_mut() {
+            //anchor the note right after the last word rather than after any trailing space
+            let offset = most_recent_verse.text().trim_end().len();
+            most_recent_verse.notes.push(Book::make_note(v, offset));
+        }
+    }
+
+    fn is_section_tag(child: &Node) -> bool {
+        child.has_tag_name(SECTION_TAG)
+    }
+
+    ///the heading text without any notes attached to it
+    fn make_heading(child: &Node) -> String {
+        let raw: String = child
+            .descendants()
+            .filter(|n| n.is_text() && n.ancestors().all(|a| Book::is_scripture_tag(&a)))
+            .filter_map(|n| n.text())
+            .collect();
+        raw.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
+
+    fn new_verse(v: &Node) -> anyhow::Result<Verse> {
+        let mut new_verse = Verse::new(0, "");
+        new_verse.number = v
+            .attribute(ID_TAG)
+            .context("no verse ID")?
+            .parse::<VerseId>()?;
+        Ok(new_verse)
+    }
+
+    ///walk everything under a paragraph adding the text to the most recent verse
+    fn add_content_to_vs(
+        &mut self,
+        v: &Node,
+        style: Style,
+        pgh: &mut Paragraph,
+    ) -> anyhow::Result<()> {
+        if Book::is_verse_tag(v) {
+            //verses can start inside of a character tag (ie. <wj>...<v id="5"/>...</wj>)
+            pgh.verses.push(Book::new_verse(v)?);
+        } else if Book::is_note_tag(v) {
+            self.add_note_to_vs(v, pgh);
+        } else if v.is_text() {
+            //find the most recent verse
+            if let Some(most_recent_verse) = pgh.verses.last_mut() {
+                //start adding contents to the verse
+                if let Some(t) = v.text() {
+                    most_recent_verse.push_text(&t.replace('\n', " "), style);
+                }
+            }
+        } else if v.is_element() && Book::is_scripture_tag(v) {
+            //character tags can be nested in any order (ie. <w><nd></nd></w> or <nd><w></w></nd>)
+            //so carry the style all the way down
+            let inner_style = Book::char_style(v, style);
+            for inner in v.children() {
+                self.add_content_to_vs(&inner, inner_style, pgh)?;
             }
         }
+        Ok(())
     }
 
     fn make_chapters(&mut self, full_book: Node) -> anyhow::Result<()> {
+        //headings wait here until the paragraph they introduce shows up
+        let mut headings: Vec<String> = Vec::new();
+
         for child in full_book.children() {
             if Book::is_chapter_tag(&child) {
                 let chapter = Book::init_chapter(&child)?;
@@ -247,34 +472,26 @@ impl Book {
                 continue;
             }
 
+            if Book::is_section_tag(&child) {
+                let heading = Book::make_heading(&child);
+                if !heading.is_empty() {
+                    headings.push(heading);
+                }
+                continue;
+            }
+
             //find a paragraph node and we can start filling the chapter text
             if Book::is_paragraph_tag(&child) {
-                let mut pgh = Paragraph { verses: Vec::new() };
+                let mut pgh = Paragraph::with_kind(Book::block_kind(&child));
+                pgh.headings = std::mem::take(&mut headings);
 
                 for (i, v) in child.children().enumerate() {
                     if i == 0 && !Book::is_verse_tag(&v) {
                         //its not a verse it is a word so make partial vs
                         let partial_vs = Verse::new(0, "");
                         pgh.verses.push(partial_vs);
-                        if Book::is_word_tag_or_text(&v) {
-                            self.add_content_to_vs(&v, &mut pgh);
-                        }
-                    } else if Book::is_verse_tag(&v) {
-                        //normal situation where a paragraph starts and ends with a verse
-                        let mut new_verse = Verse::new(0, "");
-                        new_verse.number =
-                            v.attribute(ID_TAG).context("no verse ID")?.parse::<u32>()?;
-                        pgh.verses.push(new_verse);
-                    } else if Book::is_word_tag_or_text(&v) {
-                        self.add_content_to_vs(&v, &mut pgh);
-                    } else if Book::is_nd_tag(&v) {
-                        // this will handle <nd><w></w></nd> but not the inverse
-                        for inner in v.children() {
-                            if Book::is_word_tag_or_text(&inner) {
-                                self.add_content_to_vs(&inner, &mut pgh);
-                            }
-                        }
                     }
+                    self.add_content_to_vs(&v, Style::default(), &mut pgh)?;
                 }
 
                 if let Some(c) = self.chapters.iter_mut().last() {
@@ -300,21 +517,20 @@ impl fmt::Display for Book {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{bible_as_str, get_path_to_bible_file};
-    use crate::{download_bible, Config};
+    use crate::bible::passage::Point;
     use rstest::*;
 
+    const SAMPLE: &str = include_str!("../../tests/fixtures/sample.usfx.xml");
+
     #[fixture]
     fn book_fixture() -> Book {
-        download_bible(&Config::default()).unwrap();
-        let bible_str = bible_as_str(get_path_to_bible_file(&Config::default()).unwrap()).unwrap();
-        let bible_doc = roxmltree::Document::parse(&bible_str).unwrap();
+        let bible_doc = roxmltree::Document::parse(SAMPLE).unwrap();
         Book::new("Genesis".to_string(), &bible_doc).unwrap()
     }
 
     #[rstest]
     fn new_book(book_fixture: Book) {
-        assert_eq!(book_fixture.chapters.len(), 50);
+        assert_eq!(book_fixture.chapters.len(), 3);
 
         for chpt in book_fixture.chapters {
             assert_ne!(chpt.paragraphs.len(), 0);
@@ -324,8 +540,8 @@ mod tests {
                     if i != para.verses.len() - 1 {
                         let current_verse = &para.verses[i];
                         let next_verse = &para.verses[i + 1];
-                        if current_verse.number != 0 && next_verse.number != 0 {
-                            assert!(next_verse.number - current_verse.number == 1);
+                        if !current_verse.number.is_partial() && !next_verse.number.is_partial() {
+                            assert!(next_verse.number.start - current_verse.number.end == 1);
                         }
                     }
                 }
@@ -333,6 +549,276 @@ mod tests {
         }
     }
 
+    #[test]
+    fn book_with_notes() {
+        let usfx = r#"<usfx><book id="JHN"><c id="3"/><p><v id="16"/>For this is the way God loved the world:<f caller="+"><fr>3:16 </fr><ft>Or "For God so loved the world"</ft></f> by giving his one and only Son.<x caller="-"><xo>3:16 </xo><xt>Rom 5:8</xt></x></p></book></usfx>"#;
+        let doc = roxmltree::Document::parse(usfx).unwrap();
+        let book = Book::new("John".to_string(), &doc).unwrap();
+        let vs = &book.chapters[0].paragraphs[0].verses[0];
+
+        assert_eq!(
+            vs.text(),
+            "For this is the way God loved the world: by giving his one and only Son."
+        );
+        assert_eq!(
+            vs.notes,
+            vec![
+                Note::new(
+                    "+",
+                    NoteKind::Footnote,
+                    "Or \"For God so loved the world\"",
+                    40
+                ),
+                Note::new("-", NoteKind::CrossReference, "Rom 5:8", 72),
+            ]
+        );
+    }
+
+    #[test]
+    fn book_with_styles() {
+        let usfx = r#"<usfx><book id="MAT"><c id="4"/><p><v id="10"/>Then Jesus said to him, <wj>"Go away, Satan! For it is written: '<w s="G4352">You are to worship</w> the <nd>Lord</nd> your God<add> and</add> <w s="G3000"><nd>serve</nd></w> only him.'"</wj></p></book></usfx>"#;
+        let doc = roxmltree::Document::parse(usfx).unwrap();
+        let book = Book::new("Matthew".to_string(), &doc).unwrap();
+        let vs = &book.chapters[0].paragraphs[0].verses[0];
+
+        let wj = Style {
+            words_of_jesus: true,
+            ..Style::default()
+        };
+        let styles: Vec<(&str, Style)> = vs
+            .contents
+            .iter()
+            .map(|s| (s.text.as_str(), s.style))
+            .collect();
+        assert_eq!(
+            styles,
+            vec![
+                ("Then Jesus said to him, ", Style::default()),
+                (
+                    "\"Go away, Satan! For it is written: 'You are to worship the ",
+                    wj
+                ),
+                (
+                    "Lord",
+                    Style {
+                        divine_name: true,
+                        ..wj
+                    }
+                ),
+                (" your God", wj),
+                (" and", Style { added: true, ..wj }),
+                (" ", wj),
+                (
+                    "serve",
+                    Style {
+                        divine_name: true,
+                        ..wj
+                    }
+                ),
+                (" only him.'\"", wj),
+            ]
+        );
+    }
+
+    const NESTED_USFX: &str = include_str!("../../tests/fixtures/nested_markup.xml");
+
+    ///all the text in the paragraphs of a book that isn't inside of a non-scripture tag, whitespace
+    ///is dropped since verse boundaries don't line up with the spaces in the xml
+    fn scripture_text(doc: &roxmltree::Document, id: &str) -> String {
+        let book = doc
+            .descendants()
+            .find(|n| n.has_tag_name(BOOK_TAG) && n.attribute(ID_TAG) == Some(id))
+            .unwrap();
+        let raw: String = book
+            .children()
+            .filter(Book::is_paragraph_tag)
+            .flat_map(|p| p.descendants())
+            .filter(|n| {
+                n.is_text()
+                    && n.ancestors()
+                        .all(|a| !NON_SCRIPTURE_TAGS.contains(&a.tag_name().name()))
+            })
+            .filter_map(|n| n.text())
+            .collect();
+        raw.split_whitespace().collect()
+    }
+
+    fn verse_text(book: &Book) -> String {
+        let raw: String = book
+            .chapters
+            .iter()
+            .flat_map(|c| &c.paragraphs)
+            .flat_map(|p| &p.verses)
+            .map(|v| v.text() + " ")
+            .collect();
+        raw.split_whitespace().collect()
+    }
+
+    #[rstest]
+    #[case("Matthew", "MAT")]
+    #[case("John", "JHN")]
+    fn no_scripture_dropped(#[case] title: &str, #[case] id: &str) {
+        let doc = roxmltree::Document::parse(NESTED_USFX).unwrap();
+        let book = Book::new(title.to_string(), &doc).unwrap();
+        let expected = scripture_text(&doc, id);
+
+        assert!(!expected.is_empty());
+        assert_eq!(verse_text(&book), expected);
+    }
+
+    #[test]
+    fn nested_markup() {
+        let doc = roxmltree::Document::parse(NESTED_USFX).unwrap();
+        let book = Book::new("John".to_string(), &doc).unwrap();
+        let verses = &book.chapters[0].paragraphs[0].verses;
+
+        assert_eq!(
+            verses[0].text(),
+            "Jesus replied, \"I tell you the solemn truth, unless a person is born from above, he cannot see the kingdom of God.\""
+        );
+        //the verse starts inside of the <wj>
+        assert_eq!(verses[1].number, VerseId::new(4));
+        assert!(verses[1].text().starts_with("Nicodemus said"));
+        assert!(verses[1].contents.iter().any(|s| s.style.added));
+        assert!(!verses.iter().any(|v| v.text().contains("figure")));
+        assert!(!verses.iter().any(|v| v.text().contains("remark")));
+        assert_eq!(verses[0].notes.len(), 1);
+    }
+
+    #[test]
+    fn book_with_bridges() {
+        let usfx = r#"<usfx><book id="JHN"><c id="3"/><p><v id="16"/>For God so loved the world. <v id="17-18"/>For God did not send his Son. <v id="19a"/>Now this is the basis</p><p><v id="19b"/>that the light has come.</p></book></usfx>"#;
+        let doc = roxmltree::Document::parse(usfx).unwrap();
+        let book = Book::new("John".to_string(), &doc).unwrap();
+        let pghs = &book.chapters[0].paragraphs;
+
+        assert_eq!(pghs[0].verses[1].number, VerseId::bridge(17, 18));
+        assert_eq!(pghs[0].verses[2].number.segment, Some('a'));
+        assert_eq!(pghs[1].verses[0].number.to_string(), "19b");
+    }
+
+    #[test]
+    fn book_with_headings() {
+        let usfx = r#"<usfx><book id="JHN"><c id="3"/><s>The Conversation with Nicodemus<f caller="+"><ft>a note</ft></f></s><p><v id="1"/>Now a man of the Pharisees named Nicodemus.</p><p><v id="2"/>came to Jesus at night.</p><s>Further Testimony About Jesus</s><p><v id="22"/>After this, Jesus and his disciples came into Judean territory.</p></book></usfx>"#;
+        let doc = roxmltree::Document::parse(usfx).unwrap();
+        let book = Book::new("John".to_string(), &doc).unwrap();
+        let pghs = &book.chapters[0].paragraphs;
+
+        assert_eq!(pghs[0].headings, vec!["The Conversation with Nicodemus"]);
+        assert!(pghs[1].headings.is_empty());
+        assert_eq!(pghs[2].headings, vec!["Further Testimony About Jesus"]);
+    }
+
+    #[test]
+    fn book_with_poetry() {
+        let usfx = r#"<usfx><book id="PSA"><c id="23"/><q><v id="1"/>The LORD is my shepherd,</q><q level="2">I lack nothing.</q><b/><q><v id="2"/>He takes me to lush pastures,</q><p sfm="q3">he leads me to refreshing water.</p><p sfm="li1">a list item</p><p><v id="3"/>He restores my strength.</p></book></usfx>"#;
+        let doc = roxmltree::Document::parse(usfx).unwrap();
+        let book = Book::new("Psalms".to_string(), &doc).unwrap();
+        let kinds: Vec<BlockKind> = book.chapters[0].paragraphs.iter().map(|p| p.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::Poetry(1),
+                BlockKind::Poetry(2),
+                BlockKind::Blank,
+                BlockKind::Poetry(1),
+                BlockKind::Poetry(3),
+                BlockKind::ListItem(1),
+                BlockKind::Prose,
+            ]
+        );
+        assert!(book.chapters[0].paragraphs[2].verses.is_empty());
+    }
+
+    const PASSAGE_USFX: &str = r#"<usfx><book id="JHN"><c id="2"/><p><v id="24"/>But Jesus would not entrust himself to them, <v id="25"/>because he did not need anyone to testify about man.</p><c id="3"/><s>The Conversation with Nicodemus</s><p><v id="1"/>Now a man of the Pharisees named Nicodemus. <v id="2"/>came to Jesus at night.</p><p><v id="3"/>Jesus replied. <v id="4a"/>Nicodemus said to him,</p><q><v id="4b"/>"How can a man be born</q><q level="2">when he is old?"</q><b/><p><v id="5"/>Jesus answered.</p><c id="4"/><s>The Samaritan Woman</s><p><v id="1"/>Now when Jesus knew. <v id="2-3"/>He left Judea.</p><p><v id="4"/>But he had to pass through Samaria.</p></book></usfx>"#;
+
+    ///the verses of each paragraph of each chapter, `+` for the rest of a verse and nothing for
+    ///a stanza break
+    fn layout(passage: &Passage) -> Vec<(u32, Vec<String>)> {
+        passage
+            .chapters
+            .iter()
+            .map(|ch| {
+                let pghs = ch
+                    .paragraphs
+                    .iter()
+                    .map(|p| {
+                        let ids: Vec<String> = p
+                            .verses
+                            .iter()
+                            .map(|v| match v.number.is_partial() {
+                                true => "+".to_string(),
+                                false => v.number.to_string(),
+                            })
+                            .collect();
+                        ids.join(" ")
+                    })
+                    .collect();
+                (ch.number, pghs)
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::first_verse((3, 1), (3, 1), vec![(3, vec!["1"])])]
+    #[case::middle_of_a_paragraph((3, 2), (3, 2), vec![(3, vec!["2"])])]
+    #[case::segments((3, 4), (3, 4), vec![(3, vec!["4a", "4b", "+"])])]
+    #[case::bridge((4, 3), (4, 3), vec![(4, vec!["2-3"])])]
+    #[case::within_a_chapter((3, 2), (3, 3), vec![(3, vec!["2", "3"])])]
+    #[case::over_a_stanza((3, 3), (3, 5), vec![(3, vec!["3 4a", "4b", "+", "", "5"])])]
+    #[case::whole_chapter((3, 1), (3, 5), vec![(3, vec!["1 2", "3 4a", "4b", "+", "", "5"])])]
+    #[case::into_the_next_chapter((2, 25), (3, 1), vec![(2, vec!["25"]), (3, vec!["1"])])]
+    #[case::over_a_chapter(
+        (2, 24),
+        (4, 1),
+        vec![
+            (2, vec!["24 25"]),
+            (3, vec!["1 2", "3 4a", "4b", "+", "", "5"]),
+            (4, vec!["1"]),
+        ]
+    )]
+    #[case::nothing_there((3, 6), (3, 9), vec![])]
+    fn passages(
+        #[case] start: (u32, u32),
+        #[case] end: (u32, u32),
+        #[case] expected: Vec<(u32, Vec<&str>)>,
+    ) {
+        let doc = roxmltree::Document::parse(PASSAGE_USFX).unwrap();
+        let book = Book::new("John".to_string(), &doc).unwrap();
+        let query = Query::new(Point::new(start.0, start.1), Point::new(end.0, end.1));
+        let expected: Vec<(u32, Vec<String>)> = expected
+            .into_iter()
+            .map(|(ch, pghs)| (ch, pghs.into_iter().map(String::from).collect()))
+            .collect();
+        assert_eq!(layout(&book.passage(&query)), expected);
+    }
+
+    #[test]
+    fn passage_headings() {
+        let doc = roxmltree::Document::parse(PASSAGE_USFX).unwrap();
+        let book = Book::new("John".to_string(), &doc).unwrap();
+        let headings = |start: Point, end: Point| -> Vec<String> {
+            book.passage(&Query::new(start, end))
+                .chapters
+                .iter()
+                .flat_map(|ch| &ch.paragraphs)
+                .flat_map(|p| p.headings.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            headings(Point::new(3, 1), Point::new(3, 1)),
+            vec!["The Conversation with Nicodemus"]
+        );
+        //the heading belongs to the start of the paragraph
+        assert!(headings(Point::new(3, 2), Point::new(3, 3)).is_empty());
+        assert_eq!(
+            headings(Point::new(3, 5), Point::new(4, 1)),
+            vec!["The Samaritan Woman"]
+        );
+    }
+
     #[rstest]
     #[ignore]
     fn display_book(book_fixture: Book) {
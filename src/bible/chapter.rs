@@ -1,20 +1,115 @@
 use std::fmt;
 
-use super::paragraph::Paragraph;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use super::paragraph::{BlockKind, Paragraph};
+use super::passage::Point;
+use super::render::Renderer;
+use super::verse::VerseId;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chapter {
     pub number: u32,
     pub paragraphs: Vec<Paragraph>,
 }
 
+impl Chapter {
+    ///the number of the first verse, which isn't always 1 in a partial Bible
+    pub fn first_verse(&self) -> Option<u32> {
+        self.paragraphs
+            .iter()
+            .flat_map(|p| &p.verses)
+            .find(|v| !v.number.is_partial())
+            .map(|v| v.number.start)
+    }
+
+    ///the number of the last verse, the end of a bridge (ie. 18 for 17-18)
+    pub fn last_verse(&self) -> Option<u32> {
+        self.paragraphs
+            .iter()
+            .flat_map(|p| &p.verses)
+            .map(|v| v.number.end)
+            .max()
+    }
+
+    ///The last verse of the paragraph a verse is in, or of the whole stanza when it's a line of
+    ///poetry
+    pub fn paragraph_end(&self, verse: u32) -> Option<u32> {
+        let start = self
+            .paragraphs
+            .iter()
+            .position(|p| p.verses.iter().any(|v| v.number.contains(verse)))?;
+        let in_stanza = |kind: BlockKind| kind.is_line() && kind != BlockKind::Blank;
+        let mut end = start + 1;
+        if in_stanza(self.paragraphs[start].kind) {
+            while end < self.paragraphs.len() && in_stanza(self.paragraphs[end].kind) {
+                end += 1;
+            }
+        }
+        self.paragraphs[start..end]
+            .iter()
+            .flat_map(|p| &p.verses)
+            .map(|v| v.number.end)
+            .max()
+    }
+
+    ///The verses of the chapter from `start` up to and including `end` in the paragraphs
+    ///they're in. The rest of a verse carried over into the next paragraph comes along with it,
+    ///and a paragraph only keeps its headings when it starts inside the passage.
+    pub fn slice(&self, start: Point, end: Point) -> Chapter {
+        //a bridge (ie. 17-18) is in the passage when either end of it is
+        let in_passage = |id: &VerseId| {
+            Point::new(self.number, id.end) >= start && Point::new(self.number, id.start) <= end
+        };
+
+        let mut paragraphs: Vec<Paragraph> = Vec::new();
+        //whether the verse the text so far belongs to is in the passage
+        let mut inside = false;
+        for pgh in &self.paragraphs {
+            let starts_inside = match pgh.verses.first() {
+                Some(vs) if !vs.number.is_partial() => in_passage(&vs.number),
+                _ => inside,
+            };
+            let mut verses = Vec::new();
+            for vs in &pgh.verses {
+                if !vs.number.is_partial() {
+                    inside = in_passage(&vs.number);
+                }
+                if inside {
+                    verses.push(vs.clone());
+                }
+            }
+            //stanza breaks are kept while the passage is still going
+            if verses.is_empty() && !(pgh.verses.is_empty() && inside) {
+                continue;
+            }
+
+            let headings = if starts_inside {
+                pgh.headings.clone()
+            } else {
+                Vec::new()
+            };
+            paragraphs.push(Paragraph {
+                headings,
+                kind: pgh.kind,
+                verses,
+            });
+        }
+
+        //a break after the last verse has nothing left to break up
+        while paragraphs.last().is_some_and(|p| p.verses.is_empty()) {
+            paragraphs.pop();
+        }
+        Chapter {
+            number: self.number,
+            paragraphs,
+        }
+    }
+}
+
 impl fmt::Display for Chapter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "CHAPTER_{}", self.number)?;
-        for p in &self.paragraphs {
-            writeln!(f, "{}\n", p)?;
-        }
-        Ok(())
+        write!(f, "{}", Renderer::default().chapter(self))
     }
 }
 
@@ -25,6 +120,39 @@ mod tests {
     use rstest::*;
     use textwrap::{fill, termwidth, wrap_algorithms::Penalties, Options, WrapAlgorithm};
 
+    #[test]
+    fn paragraph_ends() {
+        let mut prose = Paragraph::default();
+        prose.verses.push(Verse::new(4, "Love is patient,"));
+        prose.verses.push(Verse::new(5, "it is not rude,"));
+        let mut line = Paragraph::with_kind(BlockKind::Poetry(1));
+        line.verses
+            .push(Verse::new(6, "It is not glad about injustice,"));
+        let mut next_line = Paragraph::with_kind(BlockKind::Poetry(2));
+        next_line
+            .verses
+            .push(Verse::new(7, "but rejoices in the truth."));
+        let mut after_break = Paragraph::with_kind(BlockKind::Poetry(1));
+        after_break.verses.push(Verse::new(8, "Love never ends."));
+        let chpt = Chapter {
+            number: 13,
+            paragraphs: vec![
+                prose,
+                line,
+                next_line,
+                Paragraph::with_kind(BlockKind::Blank),
+                after_break,
+            ],
+        };
+
+        assert_eq!(chpt.first_verse(), Some(4));
+        assert_eq!(chpt.last_verse(), Some(8));
+        assert_eq!(chpt.paragraph_end(4), Some(5));
+        assert_eq!(chpt.paragraph_end(6), Some(7));
+        assert_eq!(chpt.paragraph_end(8), Some(8));
+        assert_eq!(chpt.paragraph_end(9), None);
+    }
+
     #[test]
     fn display_chapter() {
         let mut chpt = Chapter {
@@ -32,31 +160,24 @@ mod tests {
             paragraphs: Vec::new(),
         };
 
-        let mut p1 = Paragraph { verses: Vec::new() };
-        let mut p2 = Paragraph { verses: Vec::new() };
+        let mut p1 = Paragraph::default();
+        let mut p2 = Paragraph::default();
 
         let opts =
             Options::new(termwidth()).wrap_algorithm(WrapAlgorithm::OptimalFit(Penalties::new()));
         let mut expected = "CHAPTER_11\n[34] \"Where have you put him?\" He asked. \"Come and see, Lord,\" they answered. [35] Jesus wept.\n\n[36] So the Jews said, \"See how he loved him!\"\n\n".to_string();
 
         expected = fill(&expected, opts);
-        let j_1134 = Verse {
-            number: 34,
-            contents: r#""Where have you put him?" He asked. "Come and see, Lord," they answered."#
-                .to_string(),
-        };
+        let j_1134 = Verse::new(
+            34,
+            r#""Where have you put him?" He asked. "Come and see, Lord," they answered."#,
+        );
 
-        let j_1135 = Verse {
-            number: 35,
-            contents: "Jesus wept.".to_string(),
-        };
+        let j_1135 = Verse::new(35, "Jesus wept.");
         p1.verses.push(j_1134);
         p1.verses.push(j_1135);
 
-        let j_1136 = Verse {
-            number: 36,
-            contents: r#"So the Jews said, "See how he loved him!""#.to_string(),
-        };
+        let j_1136 = Verse::new(36, r#"So the Jews said, "See how he loved him!""#);
 
         //verse is a new paragraph just for testing purposes
         p2.verses.push(j_1136);
@@ -68,6 +189,43 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn display_chapter_with_headings() {
+        let mut chpt = Chapter {
+            number: 3,
+            paragraphs: Vec::new(),
+        };
+
+        let mut p1 = Paragraph::default();
+        p1.headings
+            .push("The Conversation with Nicodemus".to_string());
+        p1.verses
+            .push(Verse::new(1, "Now a man of the Pharisees named Nicodemus."));
+
+        let mut p2 = Paragraph::default();
+        p2.headings
+            .push("Further Testimony About Jesus by John the Baptist".to_string());
+        p2.verses.push(Verse::new(
+            22,
+            "After this, Jesus and his disciples came into Judean territory.",
+        ));
+
+        chpt.paragraphs.push(p1);
+        chpt.paragraphs.push(p2);
+
+        let result = format!("{}", chpt);
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines[0], "CHAPTER_3");
+        assert_eq!(lines[1], "The Conversation with Nicodemus");
+        assert!(lines[2].starts_with("[1] Now a man"));
+        assert_eq!(lines[3], "");
+        assert_eq!(
+            lines[4],
+            "Further Testimony About Jesus by John the Baptist"
+        );
+        assert!(lines[5].starts_with("[22] After this"));
+    }
+
     #[fixture]
     fn chapter_fixture() -> Chapter {
         let mut ch = Chapter {
@@ -75,8 +233,8 @@ mod tests {
             paragraphs: Vec::new(),
         };
 
-        let mut p1 = Paragraph { verses: Vec::new() };
-        let mut p2 = Paragraph { verses: Vec::new() };
+        let mut p1 = Paragraph::default();
+        let mut p2 = Paragraph::default();
 
         p1.verses
             .push(Verse::new(1, "here is some content for v1 of paragraph 1"));
@@ -0,0 +1,419 @@
+pub mod osis;
+pub mod usfm;
+pub mod usx;
+pub mod zefania;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use super::book::Book;
+use super::chapter::Chapter;
+use super::metadata::BibleMetadata;
+use super::note::{Note, NoteKind};
+use super::paragraph::{BlockKind, Paragraph};
+use super::span::Style;
+use super::verse::{Verse, VerseId};
+use super::Bible;
+
+///how much of a file is enough to tell what format it's in
+const SNIFF_LEN: u64 = 4 * 1024;
+
+///file extensions of formats that keep each book in a file of its own
+const BOOK_EXTENSIONS: &[&str] = &["usfm", "sfm", "usx"];
+
+///A file format a Bible, or a single book of one, can be read from
+pub trait BibleSource: Sync {
+    ///the name of the format for messages (ie. USFX)
+    fn name(&self) -> &'static str;
+
+    ///does a file that starts like this look like one of ours
+    fn detect(&self, start: &str) -> bool;
+
+    fn parse(&self, contents: &str) -> anyhow::Result<Bible>;
+}
+
+///USFX from ebible.org
+pub struct Usfx;
+
+impl BibleSource for Usfx {
+    fn name(&self) -> &'static str {
+        "USFX"
+    }
+
+    fn detect(&self, start: &str) -> bool {
+        root_tag(start) == Some("usfx")
+    }
+
+    fn parse(&self, contents: &str) -> anyhow::Result<Bible> {
+        Bible::new(&xml(self, contents)?)
+    }
+}
+
+///every format kyro can read
+static SOURCES: &[&dyn BibleSource] = &[
+    &Usfx,
+    &osis::Osis,
+    &usfm::Usfm,
+    &usx::Usx,
+    &zefania::Zefania,
+];
+
+///the name of the root element of an xml document without parsing the whole thing
+fn root_tag(contents: &str) -> Option<&str> {
+    let mut rest = contents.trim_start_matches('\u{feff}');
+    loop {
+        rest = rest.trim_start();
+        if rest.starts_with("<?") {
+            rest = &rest[rest.find("?>")? + 2..];
+        } else if rest.starts_with("<!--") {
+            rest = &rest[rest.find("-->")? + 3..];
+        } else if rest.starts_with("<!") {
+            rest = &rest[rest.find('>')? + 1..];
+        } else {
+            break;
+        }
+    }
+
+    let tag = rest.strip_prefix('<')?;
+    let end = tag.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+    //ignore any namespace prefix (ie. <osis:osis>)
+    Some(tag[..end].rsplit(':').next().unwrap_or_default())
+}
+
+///parse an xml Bible saying which format it was meant to be when it's broken
+fn xml<'a>(source: &dyn BibleSource, contents: &'a str) -> anyhow::Result<roxmltree::Document<'a>> {
+    roxmltree::Document::parse(contents).context(format!("invalid {} file", source.name()))
+}
+
+///work out the format of a Bible from the start of its contents
+pub fn detect(start: &str) -> anyhow::Result<&'static dyn BibleSource> {
+    if let Some(source) = SOURCES.iter().find(|s| s.detect(start)) {
+        return Ok(*source);
+    }
+    match root_tag(start) {
+        Some(other) => anyhow::bail!("<{}> documents are not a Bible format kyro knows", other),
+        None => anyhow::bail!("cannot tell what format this Bible is in"),
+    }
+}
+
+///the format of a file from the first few kilobytes of it
+pub fn sniff(path: &Path) -> anyhow::Result<&'static dyn BibleSource> {
+    let mut start = Vec::new();
+    File::open(path)
+        .context(format!("cannot open {}", path.display()))?
+        .take(SNIFF_LEN)
+        .read_to_end(&mut start)?;
+    detect(&String::from_utf8_lossy(&start))
+}
+
+///parse a Bible in any of the formats we know
+pub fn parse_bible(contents: &str) -> anyhow::Result<Bible> {
+    detect(contents)?.parse(contents)
+}
+
+fn is_book_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| BOOK_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+///the files in a directory that each hold one book (ie. USFM or USX) sorted by name
+pub fn book_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .context(format!("cannot read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| is_book_file(p))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+///read a directory with a file for each book as a whole Bible
+pub fn parse_dir(dir: &Path) -> anyhow::Result<Bible> {
+    let mut metadata = BibleMetadata::default();
+    let mut books = Vec::new();
+    for file in book_files(dir)? {
+        let contents =
+            fs::read_to_string(&file).context(format!("cannot read {}", file.display()))?;
+        let bible = parse_bible(&contents).context(format!("cannot read {}", file.display()))?;
+        metadata = metadata.or(bible.metadata);
+        books.extend(bible.books);
+    }
+    anyhow::ensure!(!books.is_empty(), "no books found in {}", dir.display());
+    books.sort_by_key(|b| super::book::canonical_position(&b.title));
+
+    Ok(Bible::from_books(metadata, books))
+}
+
+///read a Bible from a single file or a directory with a file for each book
+pub fn read_bible(source: &Path) -> anyhow::Result<Bible> {
+    if source.is_dir() {
+        return parse_dir(source);
+    }
+    let contents =
+        fs::read_to_string(source).context(format!("cannot read {}", source.display()))?;
+    parse_bible(&contents).context(format!("cannot read {}", source.display()))
+}
+
+///every file a Bible is read from
+pub fn source_files(source: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if source.is_dir() {
+        book_files(source)
+    } else {
+        Ok(vec![source.to_path_buf()])
+    }
+}
+
+///the format of a Bible file or a directory of books, going by the first book in it
+pub fn source_format(source: &Path) -> anyhow::Result<&'static dyn BibleSource> {
+    let first = source_files(source)?
+        .into_iter()
+        .next()
+        .context(format!("there are no books in {}", source.display()))?;
+    sniff(&first)
+}
+
+///Find the Bible in a directory. A file with a whole Bible in it wins over the directory
+///itself holding a file for each book.
+pub fn find_source(dir: &Path) -> Option<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && !is_book_file(p))
+        .collect();
+    files.sort();
+    if let Some(whole) = files.into_iter().find(|p| sniff(p).is_ok()) {
+        return Some(whole);
+    }
+    match book_files(dir) {
+        Ok(books) if !books.is_empty() => Some(dir.to_path_buf()),
+        _ => None,
+    }
+}
+
+///Builds up a book one piece at a time in the order things show up in the source.
+///Formats that mark where things start rather than wrapping them up (ie. OSIS milestones) are
+///much easier to read this way.
+pub struct BookBuilder {
+    book: Book,
+    ///headings wait here until the paragraph they introduce shows up
+    headings: Vec<String>,
+    pgh: Option<Paragraph>,
+}
+
+impl BookBuilder {
+    pub fn new(title: &str, id: &str) -> Self {
+        Self {
+            book: Book {
+                title: title.to_string(),
+                id: id.to_string(),
+                chapters: Vec::new(),
+            },
+            headings: Vec::new(),
+            pgh: None,
+        }
+    }
+
+    pub fn chapter(&mut self, number: u32) {
+        self.end_paragraph();
+        self.book.chapters.push(Chapter {
+            number,
+            paragraphs: Vec::new(),
+        });
+    }
+
+    pub fn heading(&mut self, text: &str) {
+        let heading = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !heading.is_empty() {
+            self.end_paragraph();
+            self.headings.push(heading);
+        }
+    }
+
+    pub fn paragraph(&mut self, kind: BlockKind) {
+        //a verse that has nothing in it yet starts the new paragraph instead
+        let empty_verse = match self.pgh.as_mut() {
+            Some(pgh) if pgh.verses.last().is_some_and(|v| v.contents.is_empty()) => {
+                pgh.verses.pop()
+            }
+            _ => None,
+        };
+        self.end_paragraph();
+        let mut pgh = Paragraph::with_kind(kind);
+        pgh.headings = std::mem::take(&mut self.headings);
+        pgh.verses.extend(empty_verse);
+        self.pgh = Some(pgh);
+    }
+
+    ///a blank line between stanzas, nothing is needed before the first one in a chapter
+    pub fn stanza_break(&mut self) {
+        self.end_paragraph();
+        if let Some(c) = self.book.chapters.last_mut() {
+            if !c.paragraphs.is_empty() {
+                c.paragraphs.push(Paragraph::with_kind(BlockKind::Blank));
+            }
+        }
+    }
+
+    ///whitespace between one verse and the next doesn't belong to either of them
+    fn trim_last_verse(&mut self) {
+        let last_span = self
+            .pgh
+            .as_mut()
+            .and_then(|p| p.verses.last_mut())
+            .and_then(|v| v.contents.last_mut());
+        if let Some(span) = last_span {
+            span.text.truncate(span.text.trim_end().len());
+        }
+    }
+
+    pub fn end_paragraph(&mut self) {
+        self.trim_last_verse();
+        if let Some(pgh) = self.pgh.take() {
+            if pgh.verses.is_empty() && pgh.kind != BlockKind::Blank {
+                //nothing made it in so hang on to the headings for the next one
+                self.headings.splice(0..0, pgh.headings);
+                return;
+            }
+            if let Some(c) = self.book.chapters.last_mut() {
+                c.paragraphs.push(pgh);
+            }
+        }
+    }
+
+    ///the paragraph being filled, text outside of any paragraph gets a plain one
+    fn current_paragraph(&mut self) -> &mut Paragraph {
+        if self.pgh.is_none() {
+            self.paragraph(BlockKind::Prose);
+        }
+        self.pgh.get_or_insert_with(Paragraph::default)
+    }
+
+    pub fn verse(&mut self, id: VerseId) {
+        self.trim_last_verse();
+        let mut vs = Verse::new(0, "");
+        vs.number = id;
+        self.current_paragraph().verses.push(vs);
+    }
+
+    ///the verse being filled, text at the start of a paragraph carries on the verse before it
+    fn current_verse(&mut self) -> &mut Verse {
+        let pgh = self.current_paragraph();
+        if pgh.verses.is_empty() {
+            pgh.verses.push(Verse::new(0, ""));
+        }
+        pgh.verses.last_mut().expect("a verse was just added")
+    }
+
+    pub fn text(&mut self, text: &str, style: Style) {
+        if self.book.chapters.is_empty() {
+            return;
+        }
+        //whitespace between tags only counts once we are inside of a verse
+        let has_verse = self.pgh.as_ref().is_some_and(|p| !p.verses.is_empty());
+        if !has_verse && text.trim().is_empty() {
+            return;
+        }
+
+        let vs = self.current_verse();
+        let mut collapsed = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if text.starts_with(char::is_whitespace) {
+            collapsed.insert(0, ' ');
+        }
+        if text.ends_with(char::is_whitespace) && !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+        //don't double up spaces where two pieces of text meet
+        let current = vs.text();
+        if current.is_empty() || current.ends_with(' ') {
+            collapsed = collapsed.trim_start().to_string();
+        }
+        vs.push_text(&collapsed, style);
+    }
+
+    pub fn note(&mut self, kind: NoteKind, caller: &str, text: &str) {
+        if self.book.chapters.is_empty() {
+            return;
+        }
+        let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let vs = self.current_verse();
+        //anchor the note right after the last word rather than after any trailing space
+        let offset = vs.text().trim_end().len();
+        vs.notes.push(Note::new(caller, kind, &text, offset));
+    }
+
+    pub fn finish(mut self) -> Book {
+        self.end_paragraph();
+        self.book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(r#"<?xml version="1.0"?><usfx xmlns:xsi="x"><book/></usfx>"#, "USFX")]
+    #[case(
+        "\u{feff}<?xml version=\"1.0\"?>\n<!-- a comment -->\n<osis xmlns=\"http://www.bibletechnologies.net/2003/OSIS/namespace\"></osis>",
+        "OSIS"
+    )]
+    #[case("\\id JHN\n\\c 1", "USFM")]
+    #[case(r#"<usx version="3.0"><book code="JHN" style="id"/></usx>"#, "USX")]
+    #[case(r#"<XMLBIBLE biblename="Sample"></XMLBIBLE>"#, "Zefania")]
+    fn detect_format(#[case] start: &str, #[case] name: &str) {
+        assert_eq!(detect(start).unwrap().name(), name);
+    }
+
+    #[test]
+    fn unknown_formats() {
+        assert!(detect("<html></html>").is_err());
+        assert!(detect("just some text").is_err());
+    }
+
+    #[test]
+    fn find_the_source() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        assert_eq!(
+            find_source(&fixtures.join("usfm")),
+            Some(fixtures.join("usfm"))
+        );
+        assert_eq!(
+            find_source(&fixtures),
+            Some(fixtures.join("nested_markup.xml"))
+        );
+    }
+
+    #[test]
+    fn build_a_book() {
+        let mut builder = BookBuilder::new("John", "JHN");
+        builder.text("text before any chapter is dropped", Style::default());
+        builder.chapter(11);
+        builder.heading("The Death of Lazarus");
+        builder.paragraph(BlockKind::Prose);
+        builder.text("\n  ", Style::default());
+        builder.verse(VerseId::new(35));
+        builder.text("\n  Jesus ", Style::default());
+        builder.text(" wept.", Style::default());
+        builder.note(NoteKind::Footnote, "+", " a   note ");
+        builder.paragraph(BlockKind::Poetry(1));
+        builder.text("carried on", Style::default());
+        let book = builder.finish();
+
+        let pghs = &book.chapters[0].paragraphs;
+        assert_eq!(pghs.len(), 2);
+        assert_eq!(pghs[0].headings, vec!["The Death of Lazarus"]);
+        assert_eq!(pghs[0].verses[0].text(), "Jesus wept.");
+        assert_eq!(pghs[0].verses[0].notes[0].text, "a note");
+        assert_eq!(pghs[0].verses[0].notes[0].offset, 11);
+        assert!(pghs[1].verses[0].number.is_partial());
+        assert_eq!(pghs[1].kind, BlockKind::Poetry(1));
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use once_cell::sync::Lazy;
+use roxmltree::Node;
+
+use super::{root_tag, xml, BibleSource, BookBuilder};
+use crate::bible::book::{canonical_position, title_from_id};
+use crate::bible::metadata::{non_empty, BibleMetadata};
+use crate::bible::note::NoteKind;
+use crate::bible::paragraph::BlockKind;
+use crate::bible::span::Style;
+use crate::bible::verse::VerseId;
+use crate::bible::Bible;
+
+const DIV_TAG: &str = "div";
+const TYPE_ATTR: &str = "type";
+const BOOK_TYPE: &str = "book";
+const OSIS_ID_ATTR: &str = "osisID";
+// milestones mark the start and end of a chapter or verse with sID and eID instead of wrapping it
+const END_ID_ATTR: &str = "eID";
+const CHAPTER_TAG: &str = "chapter";
+const VERSE_TAG: &str = "verse";
+const P_TAG: &str = "p";
+const LINE_GROUP_TAG: &str = "lg";
+const LINE_TAG: &str = "l";
+const ITEM_TAG: &str = "item";
+const LEVEL_ATTR: &str = "level";
+const TITLE_TAG: &str = "title";
+const NOTE_TAG: &str = "note";
+const CROSS_REF_TYPE: &str = "crossReference";
+const NOTE_CALLER_ATTR: &str = "n";
+const MILESTONE_TAG: &str = "milestone";
+const PARAGRAPH_MILESTONE: &str = "x-p";
+const DIVINE_NAME_TAG: &str = "divineName";
+const QUOTE_TAG: &str = "q";
+const WHO_ATTR: &str = "who";
+const TRANS_CHANGE_TAG: &str = "transChange";
+const ADDED_TYPE: &str = "added";
+const LANGUAGE_TAG: &str = "language";
+const OSIS_TEXT_TAG: &str = "osisText";
+const WORK_TAG: &str = "work";
+const RIGHTS_TAG: &str = "rights";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+///tags that hold something other than scripture
+const NON_SCRIPTURE_TAGS: &[&str] = &["header", "figure", "reference", "index", "milestone"];
+
+///OSIS book ids to the three letter ids used everywhere else
+pub static OSIS_BOOK_ID: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    [
+        ("Gen", "GEN"),
+        ("Exod", "EXO"),
+        ("Lev", "LEV"),
+        ("Num", "NUM"),
+        ("Deut", "DEU"),
+        ("Josh", "JOS"),
+        ("Judg", "JDG"),
+        ("Ruth", "RUT"),
+        ("1Sam", "1SA"),
+        ("2Sam", "2SA"),
+        ("1Kgs", "1KI"),
+        ("2Kgs", "2KI"),
+        ("1Chr", "1CH"),
+        ("2Chr", "2CH"),
+        ("Ezra", "EZR"),
+        ("Neh", "NEH"),
+        ("Esth", "EST"),
+        ("Job", "JOB"),
+        ("Ps", "PSA"),
+        ("Prov", "PRO"),
+        ("Eccl", "ECC"),
+        ("Song", "SNG"),
+        ("Isa", "ISA"),
+        ("Jer", "JER"),
+        ("Lam", "LAM"),
+        ("Ezek", "EZK"),
+        ("Dan", "DAN"),
+        ("Hos", "HOS"),
+        ("Joel", "JOL"),
+        ("Amos", "AMO"),
+        ("Obad", "OBA"),
+        ("Jonah", "JON"),
+        ("Mic", "MIC"),
+        ("Nah", "NAM"),
+        ("Hab", "HAB"),
+        ("Zeph", "ZEP"),
+        ("Hag", "HAG"),
+        ("Zech", "ZEC"),
+        ("Mal", "MAL"),
+        ("Matt", "MAT"),
+        ("Mark", "MRK"),
+        ("Luke", "LUK"),
+        ("John", "JHN"),
+        ("Acts", "ACT"),
+        ("Rom", "ROM"),
+        ("1Cor", "1CO"),
+        ("2Cor", "2CO"),
+        ("Gal", "GAL"),
+        ("Eph", "EPH"),
+        ("Phil", "PHP"),
+        ("Col", "COL"),
+        ("1Thess", "1TH"),
+        ("2Thess", "2TH"),
+        ("1Tim", "1TI"),
+        ("2Tim", "2TI"),
+        ("Titus", "TIT"),
+        ("Phlm", "PHM"),
+        ("Heb", "HEB"),
+        ("Jas", "JAS"),
+        ("1Pet", "1PE"),
+        ("2Pet", "2PE"),
+        ("1John", "1JN"),
+        ("2John", "2JN"),
+        ("3John", "3JN"),
+        ("Jude", "JUD"),
+        ("Rev", "REV"),
+    ]
+    .iter()
+    .copied()
+    .collect()
+});
+
+fn is_book_div(node: &Node) -> bool {
+    node.has_tag_name(DIV_TAG) && node.attribute(TYPE_ATTR) == Some(BOOK_TYPE)
+}
+
+fn is_end_milestone(node: &Node) -> bool {
+    node.has_attribute(END_ID_ATTR)
+}
+
+fn tag_name<'a>(node: &Node<'a, '_>) -> &'a str {
+    node.tag_name().name()
+}
+
+///the last part of an osisID (ie. Gen.1.3 -> 3)
+fn last_number(osis_id: &str) -> anyhow::Result<u32> {
+    osis_id
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+        .parse::<u32>()
+        .context(format!("{} doesn't end with a number", osis_id))
+}
+
+///a verse can cover a few osisIDs (ie. "Gen.1.3 Gen.1.4") which makes a bridge
+fn verse_id(osis_id: &str) -> anyhow::Result<VerseId> {
+    let mut ids = osis_id.split_whitespace();
+    let start = last_number(ids.next().unwrap_or_default())?;
+    let end = match ids.last() {
+        Some(last) => last_number(last)?,
+        None => start,
+    };
+    Ok(VerseId::bridge(start, end.max(start)))
+}
+
+fn text_of(node: &Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text() && !n.ancestors().any(|a| tag_name(&a) == NOTE_TAG))
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+fn level(node: &Node) -> u8 {
+    node.attribute(LEVEL_ATTR)
+        .and_then(|l| l.parse::<u8>().ok())
+        .unwrap_or(1)
+}
+
+fn char_style(node: &Node, outer: Style) -> Style {
+    match tag_name(node) {
+        DIVINE_NAME_TAG => Style {
+            divine_name: true,
+            ..outer
+        },
+        QUOTE_TAG if node.attribute(WHO_ATTR) == Some("Jesus") => Style {
+            words_of_jesus: true,
+            ..outer
+        },
+        TRANS_CHANGE_TAG if node.attribute(TYPE_ATTR) == Some(ADDED_TYPE) => Style {
+            added: true,
+            ..outer
+        },
+        _ => outer,
+    }
+}
+
+fn walk(node: Node, style: Style, builder: &mut BookBuilder) -> anyhow::Result<()> {
+    if node.is_text() {
+        if let Some(t) = node.text() {
+            builder.text(t, style);
+        }
+        return Ok(());
+    }
+    if !node.is_element() || is_end_milestone(&node) {
+        return Ok(());
+    }
+
+    match tag_name(&node) {
+        CHAPTER_TAG => {
+            let osis_id = node
+                .attribute(OSIS_ID_ATTR)
+                .context("chapter has no osisID")?;
+            builder.chapter(last_number(osis_id)?);
+        }
+        VERSE_TAG => {
+            let osis_id = node
+                .attribute(OSIS_ID_ATTR)
+                .context("verse has no osisID")?;
+            builder.verse(verse_id(osis_id)?);
+        }
+        TITLE_TAG => {
+            //the chapter number is already printed so a title that just says "Chapter 3" can go
+            if node.attribute(TYPE_ATTR) != Some(CHAPTER_TAG) {
+                builder.heading(&text_of(&node));
+            }
+            return Ok(());
+        }
+        NOTE_TAG => {
+            let kind = if node.attribute(TYPE_ATTR) == Some(CROSS_REF_TYPE) {
+                NoteKind::CrossReference
+            } else {
+                NoteKind::Footnote
+            };
+            let text: String = node
+                .descendants()
+                .filter(|n| n.is_text())
+                .filter_map(|n| n.text())
+                .collect();
+            builder.note(
+                kind,
+                node.attribute(NOTE_CALLER_ATTR).unwrap_or_default(),
+                &text,
+            );
+            return Ok(());
+        }
+        MILESTONE_TAG if node.attribute(TYPE_ATTR) == Some(PARAGRAPH_MILESTONE) => {
+            builder.paragraph(BlockKind::Prose);
+            return Ok(());
+        }
+        P_TAG => builder.paragraph(BlockKind::Prose),
+        LINE_TAG => builder.paragraph(BlockKind::Poetry(level(&node))),
+        ITEM_TAG => builder.paragraph(BlockKind::ListItem(level(&node))),
+        LINE_GROUP_TAG => builder.stanza_break(),
+        name if NON_SCRIPTURE_TAGS.contains(&name) => return Ok(()),
+        _ => {}
+    }
+
+    let inner_style = char_style(&node, style);
+    for child in node.children() {
+        walk(child, inner_style, builder)?;
+    }
+
+    match tag_name(&node) {
+        P_TAG | LINE_TAG | ITEM_TAG => builder.end_paragraph(),
+        _ => {}
+    }
+    Ok(())
+}
+
+///OSIS from CrossWire, Open Scriptures and the like
+pub struct Osis;
+
+impl BibleSource for Osis {
+    fn name(&self) -> &'static str {
+        "OSIS"
+    }
+
+    fn detect(&self, start: &str) -> bool {
+        root_tag(start) == Some("osis")
+    }
+
+    fn parse(&self, contents: &str) -> anyhow::Result<Bible> {
+        parse(&xml(self, contents)?)
+    }
+}
+
+///read a whole OSIS document into a Bible
+pub fn parse(doc: &roxmltree::Document) -> anyhow::Result<Bible> {
+    let language = doc
+        .descendants()
+        .find(|n| n.has_tag_name(LANGUAGE_TAG))
+        .and_then(|n| n.text())
+        .or_else(|| {
+            doc.descendants()
+                .find(|n| n.has_tag_name(OSIS_TEXT_TAG))
+                .and_then(|n| n.attribute((XML_NS, "lang")))
+        })
+        .map(|code| code.trim().to_string());
+    //the first <work> in the header describes the text itself
+    let work = doc.descendants().find(|n| n.has_tag_name(WORK_TAG));
+    let work_field = |tag: &str| {
+        work.and_then(|w| w.children().find(|n| n.has_tag_name(tag)))
+            .and_then(|n| non_empty(text_of(&n).trim().to_string()))
+    };
+    let metadata = BibleMetadata {
+        title: work_field(TITLE_TAG),
+        language,
+        copyright: work_field(RIGHTS_TAG),
+        license: None,
+    };
+
+    let mut books = Vec::new();
+    for node in doc.descendants().filter(is_book_div) {
+        let osis_id = node.attribute(OSIS_ID_ATTR).unwrap_or_default();
+        //deuterocanonical books and the like aren't ones we know how to read yet
+        let (id, title) = match OSIS_BOOK_ID
+            .get(osis_id)
+            .and_then(|id| Some((*id, title_from_id(id)?)))
+        {
+            Some(found) => found,
+            None => continue,
+        };
+
+        let mut builder = BookBuilder::new(title, id);
+        for child in node.children() {
+            walk(child, Style::default(), &mut builder)?;
+        }
+        books.push(builder.finish());
+    }
+    books.sort_by_key(|b| canonical_position(&b.title));
+
+    Ok(Bible::from_books(metadata, books))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OSIS: &str = include_str!("../../../tests/fixtures/sample.osis.xml");
+
+    fn bible() -> Bible {
+        let doc = roxmltree::Document::parse(OSIS).unwrap();
+        parse(&doc).unwrap()
+    }
+
+    #[test]
+    fn osis_books() {
+        let bible = bible();
+        let titles: Vec<&str> = bible.books.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, vec!["Psalms", "John"]);
+        assert_eq!(bible.metadata.language, Some("en".to_string()));
+        assert_eq!(bible.metadata.title.as_deref(), Some("A Sample Bible"));
+        assert_eq!(
+            bible.metadata.copyright.as_deref(),
+            Some("Public domain. Copy freely.")
+        );
+    }
+
+    #[test]
+    fn osis_milestones() {
+        let bible = bible();
+        let john = bible.get("John").unwrap();
+        let ch = &john.chapters[0];
+
+        assert_eq!(ch.number, 3);
+        assert_eq!(
+            ch.paragraphs[0].headings,
+            vec!["The Conversation with Nicodemus"]
+        );
+        let verses = &ch.paragraphs[0].verses;
+        assert_eq!(verses[0].number, VerseId::new(16));
+        assert_eq!(
+            verses[0].text(),
+            "For this is the way God loved the world: by giving his one and only Son."
+        );
+        assert_eq!(verses[0].notes[0].kind, NoteKind::Footnote);
+        assert_eq!(verses[0].notes[0].text, "Or \"only begotten\"");
+        assert_eq!(verses[1].number, VerseId::bridge(17, 18));
+        assert!(verses[1].contents.iter().any(|s| s.style.words_of_jesus));
+        assert_eq!(ch.paragraphs[1].verses[0].number, VerseId::new(19));
+    }
+
+    #[test]
+    fn osis_poetry() {
+        let bible = bible();
+        let psalm = &bible.get("Psalms").unwrap().chapters[0];
+        let kinds: Vec<BlockKind> = psalm.paragraphs.iter().map(|p| p.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::Poetry(1),
+                BlockKind::Poetry(2),
+                BlockKind::Blank,
+                BlockKind::Poetry(1)
+            ]
+        );
+        assert_eq!(psalm.paragraphs[0].headings, vec!["A psalm of David."]);
+        assert_eq!(
+            psalm.paragraphs[0].verses[0].text(),
+            "The LORD is my shepherd,"
+        );
+        assert!(psalm.paragraphs[0].verses[0]
+            .contents
+            .iter()
+            .any(|s| s.style.divine_name && s.text == "LORD"));
+        assert!(psalm.paragraphs[1].verses[0].number.is_partial());
+    }
+}
@@ -0,0 +1,493 @@
+use anyhow::Context;
+
+use super::{BibleSource, BookBuilder};
+use crate::bible::book::{title_from_id, Book};
+use crate::bible::metadata::BibleMetadata;
+use crate::bible::note::NoteKind;
+use crate::bible::paragraph::BlockKind;
+use crate::bible::span::Style;
+use crate::bible::verse::VerseId;
+use crate::bible::Bible;
+
+const ID_MARKER: &str = "id";
+const CHAPTER_MARKER: &str = "c";
+const VERSE_MARKER: &str = "v";
+pub(super) const BLANK_LINE_MARKER: &str = "b";
+const WORDS_OF_JESUS_MARKER: &str = "wj";
+const NAME_DEITY_MARKER: &str = "nd";
+const ADDED_MARKER: &str = "add";
+const FOOTNOTE_MARKER: &str = "f";
+const ENDNOTE_MARKER: &str = "fe";
+pub(super) const CROSS_REF_MARKER: &str = "x";
+pub(super) const FOOTNOTE_ORIGIN_MARKER: &str = "fr";
+pub(super) const CROSS_REF_ORIGIN_MARKER: &str = "xo";
+///attributes on a character marker (ie. `\w gracious|lemma="grace"\w*`) start after this
+const ATTRIBUTE_SEPARATOR: char = '|';
+
+///paragraph markers that hold plain prose
+const PROSE_MARKERS: &[&str] = &[
+    "p", "m", "po", "pr", "cls", "pmo", "pm", "pmc", "pmr", "pi", "pi1", "pi2", "pi3", "mi", "nb",
+    "pc", "ph", "ph1", "ph2", "ph3", "qr", "qc", "qd",
+];
+
+///markers whose text is a heading above a paragraph
+pub(super) const HEADING_MARKERS: &[&str] = &[
+    "s", "s1", "s2", "s3", "s4", "ms", "ms1", "ms2", "ms3", "d", "sp", "qa",
+];
+
+///paragraph markers for the identification, introduction and titles which aren't scripture
+pub(super) const NON_SCRIPTURE_MARKERS: &[&str] = &[
+    "ide", "h", "h1", "h2", "h3", "toc1", "toc2", "toc3", "toca1", "toca2", "toca3", "rem", "sts",
+    "usfm", "mt", "mt1", "mt2", "mt3", "mt4", "mte", "mte1", "mte2", "imt", "imt1", "imt2", "is",
+    "is1", "is2", "ip", "ipi", "im", "imi", "ipq", "imq", "ipr", "iq", "iq1", "iq2", "ib", "ili",
+    "ili1", "ili2", "iot", "io", "io1", "io2", "io3", "ior", "iex", "imte", "ie", "mr", "sr", "r",
+    "cl", "cp", "cd",
+];
+
+///character markers that hold something other than scripture
+pub(super) const NON_SCRIPTURE_CHAR_MARKERS: &[&str] =
+    &["va", "vp", "ca", "fig", "rq", "cat", "jmp"];
+
+///One piece of a USFM file
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    ///a marker without its backslash, a closing marker keeps its `*` (ie. `wj*`)
+    Marker(&'a str),
+    Text(&'a str),
+}
+
+///split USFM into markers and the text between them
+fn tokenize(contents: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = contents;
+    while !rest.is_empty() {
+        match rest.strip_prefix('\\') {
+            Some(after) => {
+                let mut end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '+' || c == '-'))
+                    .unwrap_or(after.len());
+                if after[end..].starts_with('*') {
+                    end += 1;
+                }
+                tokens.push(Token::Marker(&after[..end]));
+                rest = &after[end..];
+                //a single space ends an opening marker and isn't part of the text
+                if !tokens_last_is_closing(&tokens) {
+                    if let Some(c) = rest.chars().next().filter(|c| c.is_whitespace()) {
+                        rest = &rest[c.len_utf8()..];
+                    }
+                }
+            }
+            None => {
+                let end = rest.find('\\').unwrap_or(rest.len());
+                tokens.push(Token::Text(&rest[..end]));
+                rest = &rest[end..];
+            }
+        }
+    }
+    tokens
+}
+
+fn tokens_last_is_closing(tokens: &[Token]) -> bool {
+    matches!(tokens.last(), Some(Token::Marker(m)) if m.ends_with('*'))
+}
+
+///what the text that follows a marker is for
+enum Mode {
+    Scripture,
+    ///the chapter number
+    Chapter,
+    ///the verse number and then the start of the verse
+    Verse,
+    Heading(String),
+    Note {
+        kind: NoteKind,
+        caller: Option<String>,
+        text: String,
+        ///inside of the reference the note is about (ie. `\fr 3:16`)
+        in_origin: bool,
+    },
+    ///skip everything until the next paragraph
+    Ignore,
+}
+
+pub(super) fn block_kind(marker: &str) -> Option<BlockKind> {
+    if PROSE_MARKERS.contains(&marker) {
+        Some(BlockKind::Prose)
+    } else if let Some(level) = Book::marker_level(marker, "q") {
+        Some(BlockKind::Poetry(level))
+    } else {
+        Book::marker_level(marker, "li").map(BlockKind::ListItem)
+    }
+}
+
+pub(super) fn char_style(marker: &str, outer: Style) -> Style {
+    match marker {
+        WORDS_OF_JESUS_MARKER => Style {
+            words_of_jesus: true,
+            ..outer
+        },
+        NAME_DEITY_MARKER => Style {
+            divine_name: true,
+            ..outer
+        },
+        ADDED_MARKER => Style {
+            added: true,
+            ..outer
+        },
+        _ => outer,
+    }
+}
+
+///the book id from the `\id` line
+fn book_id(contents: &str) -> anyhow::Result<&str> {
+    contents
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .strip_prefix("\\id")
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .and_then(|rest| rest.split_whitespace().next())
+        .context("USFM files need to start with an \\id marker")
+}
+
+///Reads the tokens of one book into a BookBuilder
+struct Parser {
+    builder: BookBuilder,
+    mode: Mode,
+    ///character styles that are open, nested ones (ie. `\+nd`) go on top
+    styles: Vec<(String, Style)>,
+    ///inside of a character marker that isn't scripture
+    skipping: Option<String>,
+}
+
+impl Parser {
+    fn style(&self) -> Style {
+        self.styles.last().map(|(_, s)| *s).unwrap_or_default()
+    }
+
+    ///finish off whatever the last marker started
+    fn flush(&mut self) {
+        if let Mode::Heading(heading) = std::mem::replace(&mut self.mode, Mode::Scripture) {
+            self.builder.heading(&heading);
+        }
+    }
+
+    fn new_paragraph(&mut self, kind: BlockKind) {
+        self.flush();
+        self.styles.clear();
+        match kind {
+            BlockKind::Blank => self.builder.stanza_break(),
+            _ => self.builder.paragraph(kind),
+        }
+    }
+
+    fn text(&mut self, text: &str) -> anyhow::Result<()> {
+        if self.skipping.is_some() {
+            return Ok(());
+        }
+        match &mut self.mode {
+            Mode::Scripture => {
+                let text = match text.find(ATTRIBUTE_SEPARATOR) {
+                    Some(i) if !self.styles.is_empty() => &text[..i],
+                    _ => text,
+                };
+                let style = self.style();
+                self.builder.text(text, style);
+            }
+            Mode::Chapter => {
+                let number = text.split_whitespace().next().unwrap_or_default();
+                let number = number
+                    .parse::<u32>()
+                    .context(format!("{} is not a chapter number", number))?;
+                self.builder.chapter(number);
+                self.mode = Mode::Scripture;
+            }
+            Mode::Verse => {
+                let text = text.trim_start();
+                let end = text.find(char::is_whitespace).unwrap_or(text.len());
+                let id: VerseId = text[..end].parse()?;
+                self.builder.verse(id);
+                self.mode = Mode::Scripture;
+                let style = self.style();
+                self.builder.text(&text[end..], style);
+            }
+            Mode::Heading(heading) => heading.push_str(text),
+            Mode::Note {
+                caller,
+                text: note,
+                in_origin,
+                ..
+            } => {
+                let mut text = text;
+                if caller.is_none() {
+                    let trimmed = text.trim_start();
+                    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+                    *caller = Some(trimmed[..end].to_string());
+                    text = &trimmed[end..];
+                }
+                if !*in_origin {
+                    note.push_str(text);
+                }
+            }
+            Mode::Ignore => {}
+        }
+        Ok(())
+    }
+
+    fn note_marker(&mut self, marker: &str) {
+        let end_of_note = matches!(
+            marker.strip_suffix('*'),
+            Some(FOOTNOTE_MARKER | ENDNOTE_MARKER | CROSS_REF_MARKER)
+        );
+        if let Mode::Note {
+            kind,
+            caller,
+            text,
+            in_origin,
+        } = &mut self.mode
+        {
+            if end_of_note {
+                let caller = caller.take().unwrap_or_default();
+                self.builder.note(*kind, &caller, text);
+                self.mode = Mode::Scripture;
+            } else if !marker.ends_with('*') {
+                //every marker in a note starts a new part of it (ie. `\fr`, `\ft`, `\fq`)
+                *in_origin = marker == FOOTNOTE_ORIGIN_MARKER || marker == CROSS_REF_ORIGIN_MARKER;
+                if !text.is_empty() && !text.ends_with(' ') {
+                    text.push(' ');
+                }
+            }
+        }
+    }
+
+    fn marker(&mut self, marker: &str) {
+        if matches!(self.mode, Mode::Note { .. }) {
+            self.note_marker(marker);
+            return;
+        }
+
+        //nested character markers (ie. `\+nd`) work the same as the outer ones
+        let marker = marker.trim_start_matches('+');
+        if let Some(closing) = marker.strip_suffix('*') {
+            if self.skipping.as_deref() == Some(closing) {
+                self.skipping = None;
+            } else if let Some(i) = self.styles.iter().rposition(|(m, _)| m == closing) {
+                self.styles.truncate(i);
+            }
+            return;
+        }
+        if self.skipping.is_some() {
+            return;
+        }
+
+        match marker {
+            ID_MARKER => {
+                self.flush();
+                self.mode = Mode::Ignore;
+            }
+            CHAPTER_MARKER => {
+                self.flush();
+                self.styles.clear();
+                self.mode = Mode::Chapter;
+            }
+            VERSE_MARKER => {
+                self.flush();
+                self.mode = Mode::Verse;
+            }
+            FOOTNOTE_MARKER | ENDNOTE_MARKER | CROSS_REF_MARKER => {
+                let kind = if marker == CROSS_REF_MARKER {
+                    NoteKind::CrossReference
+                } else {
+                    NoteKind::Footnote
+                };
+                self.flush();
+                self.mode = Mode::Note {
+                    kind,
+                    caller: None,
+                    text: String::new(),
+                    in_origin: false,
+                };
+            }
+            BLANK_LINE_MARKER => self.new_paragraph(BlockKind::Blank),
+            m if HEADING_MARKERS.contains(&m) => {
+                self.flush();
+                self.mode = Mode::Heading(String::new());
+            }
+            m if NON_SCRIPTURE_MARKERS.contains(&m) => {
+                self.flush();
+                self.mode = Mode::Ignore;
+            }
+            m if NON_SCRIPTURE_CHAR_MARKERS.contains(&m) => self.skipping = Some(m.to_string()),
+            m => match block_kind(m) {
+                Some(kind) => self.new_paragraph(kind),
+                //anything else is a character marker that wraps scripture (ie. `\w`, `\k`, `\bk`)
+                None => {
+                    let style = char_style(m, self.style());
+                    self.styles.push((m.to_string(), style));
+                }
+            },
+        }
+    }
+}
+
+///plain USFM straight out of Paratext, one book per file
+pub struct Usfm;
+
+impl BibleSource for Usfm {
+    fn name(&self) -> &'static str {
+        "USFM"
+    }
+
+    fn detect(&self, start: &str) -> bool {
+        book_id(start).is_ok()
+    }
+
+    fn parse(&self, contents: &str) -> anyhow::Result<Bible> {
+        //front matter, glossaries and deuterocanonical books aren't ones we know how to read yet
+        if title_from_id(book_id(contents)?).is_none() {
+            return Ok(Bible::from_books(BibleMetadata::default(), Vec::new()));
+        }
+        Ok(Bible::from_books(
+            BibleMetadata::default(),
+            vec![parse_book(contents)?],
+        ))
+    }
+}
+
+///read one USFM book
+pub fn parse_book(contents: &str) -> anyhow::Result<Book> {
+    let id = book_id(contents)?;
+    let title = title_from_id(id).context(format!("{} is not a book of the Bible", id))?;
+
+    let mut parser = Parser {
+        builder: BookBuilder::new(title, id),
+        mode: Mode::Ignore,
+        styles: Vec::new(),
+        skipping: None,
+    };
+    for token in tokenize(contents) {
+        match token {
+            Token::Marker(m) => parser.marker(m),
+            Token::Text(t) => parser.text(t)?,
+        }
+    }
+    parser.flush();
+    Ok(parser.builder.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JOHN: &str = include_str!("../../../tests/fixtures/usfm/44JHNsample.usfm");
+    const PSALMS: &str = include_str!("../../../tests/fixtures/usfm/19PSAsample.usfm");
+
+    #[test]
+    fn tokenize_markers() {
+        assert_eq!(
+            tokenize("\\v 1 God \\wj so\\wj* loved"),
+            vec![
+                Token::Marker("v"),
+                Token::Text("1 God "),
+                Token::Marker("wj"),
+                Token::Text("so"),
+                Token::Marker("wj*"),
+                Token::Text(" loved"),
+            ]
+        );
+        assert_eq!(
+            tokenize("\\q1\n\\+nd Lord\\+nd*"),
+            vec![
+                Token::Marker("q1"),
+                Token::Marker("+nd"),
+                Token::Text("Lord"),
+                Token::Marker("+nd*"),
+            ]
+        );
+    }
+
+    #[test]
+    fn usfm_book() {
+        let book = parse_book(JOHN).unwrap();
+        assert_eq!(book.title, "John");
+        assert_eq!(book.id, "JHN");
+
+        let ch = &book.chapters[0];
+        assert_eq!(ch.number, 3);
+        assert_eq!(
+            ch.paragraphs[0].headings,
+            vec!["The Conversation with Nicodemus"]
+        );
+
+        let verses = &ch.paragraphs[0].verses;
+        assert_eq!(verses[0].number, VerseId::new(16));
+        assert_eq!(
+            verses[0].text(),
+            "For this is the way God loved the world: by giving his one and only Son."
+        );
+        let note = &verses[0].notes[0];
+        assert_eq!(note.kind, NoteKind::Footnote);
+        assert_eq!(note.caller, "+");
+        assert_eq!(note.text, "Or \"only begotten\"");
+        assert_eq!(note.offset, 67);
+
+        assert_eq!(verses[1].number, VerseId::bridge(17, 18));
+        assert_eq!(
+            verses[1].text(),
+            "Jesus said, For God did not send his Son into the world."
+        );
+        assert!(verses[1]
+            .contents
+            .iter()
+            .any(|s| s.style.words_of_jesus && s.text == "For God did not send his Son"));
+        assert_eq!(verses[1].notes[0].kind, NoteKind::CrossReference);
+        assert_eq!(verses[1].notes[0].text, "Jn 1:4");
+
+        assert_eq!(
+            ch.paragraphs[1].verses[0].text(),
+            "Now this is the basis for judging."
+        );
+    }
+
+    #[test]
+    fn usfm_poetry() {
+        let book = parse_book(PSALMS).unwrap();
+        let psalm = &book.chapters[0];
+        let kinds: Vec<BlockKind> = psalm.paragraphs.iter().map(|p| p.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::Poetry(1),
+                BlockKind::Poetry(2),
+                BlockKind::Blank,
+                BlockKind::Poetry(1)
+            ]
+        );
+        assert_eq!(psalm.paragraphs[0].headings, vec!["A psalm of David."]);
+        assert_eq!(
+            psalm.paragraphs[0].verses[0].text(),
+            "The LORD is my shepherd,"
+        );
+        assert!(psalm.paragraphs[0].verses[0]
+            .contents
+            .iter()
+            .any(|s| s.style.divine_name && s.text == "LORD"));
+        assert!(psalm.paragraphs[1].verses[0].number.is_partial());
+        assert_eq!(psalm.paragraphs[1].verses[0].text(), "I lack nothing.");
+    }
+
+    #[test]
+    fn usfm_dir() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/usfm");
+        let bible = super::super::parse_dir(&dir).unwrap();
+        let titles: Vec<&str> = bible.books.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, vec!["Psalms", "John"]);
+    }
+
+    #[test]
+    fn usfm_needs_an_id() {
+        assert!(parse_book("\\c 1\n\\p\n\\v 1 In the beginning").is_err());
+        assert!(parse_book("\\id XYZ\n\\c 1").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::Context;
+use roxmltree::Node;
+
+use super::usfm::{
+    block_kind, char_style, BLANK_LINE_MARKER, CROSS_REF_MARKER, CROSS_REF_ORIGIN_MARKER,
+    FOOTNOTE_ORIGIN_MARKER, HEADING_MARKERS, NON_SCRIPTURE_CHAR_MARKERS, NON_SCRIPTURE_MARKERS,
+};
+use super::{root_tag, xml, BibleSource, BookBuilder};
+use crate::bible::book::title_from_id;
+use crate::bible::metadata::BibleMetadata;
+use crate::bible::note::NoteKind;
+use crate::bible::paragraph::BlockKind;
+use crate::bible::span::Style;
+use crate::bible::verse::VerseId;
+use crate::bible::Bible;
+
+const USX_TAG: &str = "usx";
+const BOOK_TAG: &str = "book";
+const CODE_ATTR: &str = "code";
+const CHAPTER_TAG: &str = "chapter";
+const VERSE_TAG: &str = "verse";
+const NUMBER_ATTR: &str = "number";
+// USX 3 marks where chapters and verses end with an eid milestone
+const END_ID_ATTR: &str = "eid";
+const PARA_TAG: &str = "para";
+const CHAR_TAG: &str = "char";
+const NOTE_TAG: &str = "note";
+const CALLER_ATTR: &str = "caller";
+///every element says which USFM marker it came from in here
+const STYLE_ATTR: &str = "style";
+
+///elements that hold something other than scripture
+const NON_SCRIPTURE_TAGS: &[&str] = &["figure", "optbreak", "ms", "sidebar", "periph"];
+
+///USX from the Digital Bible Library, one book per file
+pub struct Usx;
+
+impl BibleSource for Usx {
+    fn name(&self) -> &'static str {
+        "USX"
+    }
+
+    fn detect(&self, start: &str) -> bool {
+        root_tag(start) == Some(USX_TAG)
+    }
+
+    fn parse(&self, contents: &str) -> anyhow::Result<Bible> {
+        let doc = xml(self, contents)?;
+        let code = doc
+            .descendants()
+            .find(|n| n.has_tag_name(BOOK_TAG))
+            .and_then(|n| n.attribute(CODE_ATTR))
+            .context("USX files need a <book> with a code")?;
+        //front matter, glossaries and deuterocanonical books aren't ones we know how to read yet
+        let title = match title_from_id(code) {
+            Some(title) => title,
+            None => return Ok(Bible::from_books(BibleMetadata::default(), Vec::new())),
+        };
+
+        let mut builder = BookBuilder::new(title, code);
+        for child in doc.root_element().children() {
+            walk(child, Style::default(), &mut builder)?;
+        }
+        Ok(Bible::from_books(
+            BibleMetadata::default(),
+            vec![builder.finish()],
+        ))
+    }
+}
+
+fn style_of<'a>(node: &Node<'a, '_>) -> &'a str {
+    node.attribute(STYLE_ATTR).unwrap_or_default()
+}
+
+fn text_of(node: &Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text() && !n.ancestors().any(|a| a.has_tag_name(NOTE_TAG)))
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+///the text of a note without the reference it's about (ie. `<char style="fr">3:16</char>`)
+fn note_text(node: &Node) -> String {
+    let is_origin = |n: &Node| {
+        n.has_tag_name(CHAR_TAG)
+            && matches!(
+                style_of(n),
+                FOOTNOTE_ORIGIN_MARKER | CROSS_REF_ORIGIN_MARKER
+            )
+    };
+    node.descendants()
+        .filter(|n| n.is_text() && !n.ancestors().any(|a| is_origin(&a)))
+        .filter_map(|n| n.text())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn walk(node: Node, style: Style, builder: &mut BookBuilder) -> anyhow::Result<()> {
+    if node.is_text() {
+        if let Some(t) = node.text() {
+            builder.text(t, style);
+        }
+        return Ok(());
+    }
+    if !node.is_element() || node.has_attribute(END_ID_ATTR) {
+        return Ok(());
+    }
+
+    let marker = style_of(&node);
+    match node.tag_name().name() {
+        BOOK_TAG => {}
+        CHAPTER_TAG => {
+            let number = node
+                .attribute(NUMBER_ATTR)
+                .context("chapter has no number")?;
+            builder.chapter(
+                number
+                    .parse()
+                    .context(format!("{} is not a chapter number", number))?,
+            );
+        }
+        VERSE_TAG => {
+            let number = node.attribute(NUMBER_ATTR).context("verse has no number")?;
+            builder.verse(number.parse::<VerseId>()?);
+        }
+        PARA_TAG if HEADING_MARKERS.contains(&marker) => builder.heading(&text_of(&node)),
+        PARA_TAG if marker == BLANK_LINE_MARKER => builder.stanza_break(),
+        PARA_TAG if NON_SCRIPTURE_MARKERS.contains(&marker) => {}
+        PARA_TAG => {
+            builder.paragraph(block_kind(marker).unwrap_or(BlockKind::Prose));
+            for child in node.children() {
+                walk(child, style, builder)?;
+            }
+            builder.end_paragraph();
+        }
+        CHAR_TAG if NON_SCRIPTURE_CHAR_MARKERS.contains(&marker) => {}
+        NOTE_TAG => {
+            let kind = if marker == CROSS_REF_MARKER {
+                NoteKind::CrossReference
+            } else {
+                NoteKind::Footnote
+            };
+            let caller = node.attribute(CALLER_ATTR).unwrap_or_default();
+            builder.note(kind, caller, &note_text(&node));
+        }
+        name if NON_SCRIPTURE_TAGS.contains(&name) => {}
+        //character styles and anything else that wraps scripture (ie. tables)
+        _ => {
+            let inner_style = if node.has_tag_name(CHAR_TAG) {
+                char_style(marker, style)
+            } else {
+                style
+            };
+            for child in node.children() {
+                walk(child, inner_style, builder)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JOHN: &str = include_str!("../../../tests/fixtures/usx/JHN.usx");
+
+    #[test]
+    fn usx_book() {
+        let bible = Usx.parse(JOHN).unwrap();
+        let book = bible.get("John").unwrap();
+        assert_eq!(book.id, "JHN");
+
+        let ch = &book.chapters[0];
+        assert_eq!(ch.number, 3);
+        assert_eq!(
+            ch.paragraphs[0].headings,
+            vec!["The Conversation with Nicodemus"]
+        );
+
+        let verses = &ch.paragraphs[0].verses;
+        assert_eq!(verses[0].number, VerseId::new(16));
+        assert_eq!(
+            verses[0].text(),
+            "For this is the way God loved the world: by giving his one and only Son."
+        );
+        assert_eq!(verses[0].notes[0].caller, "+");
+        assert_eq!(verses[0].notes[0].text, "Or \"only begotten\"");
+        assert_eq!(verses[0].notes[0].offset, 67);
+
+        assert_eq!(verses[1].number, VerseId::bridge(17, 18));
+        assert!(verses[1]
+            .contents
+            .iter()
+            .any(|s| s.style.words_of_jesus && s.text == "For God did not send his Son"));
+        assert_eq!(verses[1].notes[0].kind, NoteKind::CrossReference);
+        assert_eq!(verses[1].notes[0].text, "Jn 1:4");
+
+        let poetry = &ch.paragraphs[1];
+        assert_eq!(poetry.kind, BlockKind::Poetry(1));
+        assert_eq!(
+            poetry.verses[0].text(),
+            "Now this is the basis for judging."
+        );
+    }
+
+    #[test]
+    fn usx_front_matter_is_skipped() {
+        let bible = Usx
+            .parse(r#"<usx version="3.0"><book code="FRT" style="id"/><para style="mt1">Sample</para></usx>"#)
+            .unwrap();
+        assert!(bible.books.is_empty());
+    }
+}
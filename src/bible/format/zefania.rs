@@ -0,0 +1,232 @@
+use anyhow::Context;
+use roxmltree::Node;
+
+use super::{root_tag, xml, BibleSource, BookBuilder};
+use crate::bible::book::{canonical_position, BOOK_ORDER, BOOK_TITLE_ID};
+use crate::bible::metadata::{non_empty, BibleMetadata};
+use crate::bible::note::NoteKind;
+use crate::bible::paragraph::BlockKind;
+use crate::bible::span::Style;
+use crate::bible::verse::VerseId;
+use crate::bible::Bible;
+
+const ROOT_TAG: &str = "XMLBIBLE";
+const INFORMATION_TAG: &str = "INFORMATION";
+const TITLE_TAG: &str = "title";
+const LANGUAGE_TAG: &str = "language";
+const RIGHTS_TAG: &str = "rights";
+const BOOK_TAG: &str = "BIBLEBOOK";
+///books are numbered in the Protestant order starting from 1 for Genesis
+const BOOK_NUM_ATTR: &str = "bnumber";
+const CHAPTER_TAG: &str = "CHAPTER";
+const CHAPTER_NUM_ATTR: &str = "cnumber";
+const VERSE_TAG: &str = "VERS";
+const VERSE_NUM_ATTR: &str = "vnumber";
+const CAPTION_TAG: &str = "CAPTION";
+const NOTE_TAG: &str = "NOTE";
+const CROSS_REF_TAG: &str = "XREF";
+const CROSS_REF_SCOPE_ATTR: &str = "fscope";
+const BREAK_TAG: &str = "BR";
+const BREAK_KIND_ATTR: &str = "art";
+const PARAGRAPH_BREAK: &str = "x-p";
+const STYLE_TAG: &str = "STYLE";
+const FONT_STYLE_ATTR: &str = "fs";
+const CSS_ATTR: &str = "css";
+
+///elements that hold something other than scripture
+const NON_SCRIPTURE_TAGS: &[&str] = &[INFORMATION_TAG, "PROLOG", "REMARK", "MEDIA"];
+
+///Zefania XML used by a lot of the Bibles in languages other than English
+pub struct Zefania;
+
+impl BibleSource for Zefania {
+    fn name(&self) -> &'static str {
+        "Zefania"
+    }
+
+    fn detect(&self, start: &str) -> bool {
+        root_tag(start).is_some_and(|tag| tag.eq_ignore_ascii_case(ROOT_TAG))
+    }
+
+    fn parse(&self, contents: &str) -> anyhow::Result<Bible> {
+        let doc = xml(self, contents)?;
+        let info = doc.descendants().find(|n| n.has_tag_name(INFORMATION_TAG));
+        let info_field = |tag: &str| {
+            info.and_then(|i| i.children().find(|n| n.has_tag_name(tag)))
+                .and_then(|n| non_empty(text_of(&n).trim().to_string()))
+        };
+        let metadata = BibleMetadata {
+            title: info_field(TITLE_TAG),
+            language: info_field(LANGUAGE_TAG),
+            copyright: info_field(RIGHTS_TAG),
+            license: None,
+        };
+
+        let mut books = Vec::new();
+        for node in doc.descendants().filter(|n| n.has_tag_name(BOOK_TAG)) {
+            let number = node
+                .attribute(BOOK_NUM_ATTR)
+                .context("BIBLEBOOK has no bnumber")?;
+            //the apocrypha are numbered past Revelation and aren't ones we know how to read yet
+            let title = match number.parse::<u32>().ok().and_then(|n| BOOK_ORDER.get(&n)) {
+                Some(title) => title,
+                None => continue,
+            };
+            let id = BOOK_TITLE_ID
+                .get(title)
+                .context(format!("{} has no id", title))?;
+
+            let mut builder = BookBuilder::new(title, id);
+            for child in node.children() {
+                walk(child, Style::default(), &mut builder)?;
+            }
+            books.push(builder.finish());
+        }
+        books.sort_by_key(|b| canonical_position(&b.title));
+
+        Ok(Bible::from_books(metadata, books))
+    }
+}
+
+fn char_style(node: &Node, outer: Style) -> Style {
+    let css = node
+        .attribute(CSS_ATTR)
+        .unwrap_or_default()
+        .replace(' ', "");
+    match node.attribute(FONT_STYLE_ATTR) {
+        Some("divineName") => Style {
+            divine_name: true,
+            ..outer
+        },
+        Some("italic") => Style {
+            added: true,
+            ..outer
+        },
+        //words of Jesus are only ever marked by colouring them red
+        _ if css.contains("color:red") || css.contains("color:#ff0000") => Style {
+            words_of_jesus: true,
+            ..outer
+        },
+        _ => outer,
+    }
+}
+
+fn text_of(node: &Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+fn walk(node: Node, style: Style, builder: &mut BookBuilder) -> anyhow::Result<()> {
+    if node.is_text() {
+        if let Some(t) = node.text() {
+            builder.text(t, style);
+        }
+        return Ok(());
+    }
+    if !node.is_element() {
+        return Ok(());
+    }
+
+    match node.tag_name().name() {
+        CHAPTER_TAG => {
+            let number = node
+                .attribute(CHAPTER_NUM_ATTR)
+                .context("CHAPTER has no cnumber")?;
+            builder.chapter(
+                number
+                    .parse()
+                    .context(format!("{} is not a chapter number", number))?,
+            );
+            //paragraphs are rare in Zefania so a chapter starts off as one big one
+            builder.paragraph(BlockKind::Prose);
+        }
+        VERSE_TAG => {
+            let number = node
+                .attribute(VERSE_NUM_ATTR)
+                .context("VERS has no vnumber")?;
+            builder.verse(number.parse::<VerseId>()?);
+        }
+        CAPTION_TAG => {
+            builder.heading(&text_of(&node));
+            return Ok(());
+        }
+        NOTE_TAG => {
+            builder.note(NoteKind::Footnote, "", &text_of(&node));
+            return Ok(());
+        }
+        CROSS_REF_TAG => {
+            let text = node
+                .attribute(CROSS_REF_SCOPE_ATTR)
+                .map(str::to_string)
+                .unwrap_or_else(|| text_of(&node));
+            builder.note(NoteKind::CrossReference, "", &text);
+            return Ok(());
+        }
+        BREAK_TAG => {
+            if node.attribute(BREAK_KIND_ATTR) == Some(PARAGRAPH_BREAK) {
+                builder.paragraph(BlockKind::Prose);
+            }
+            return Ok(());
+        }
+        name if NON_SCRIPTURE_TAGS.contains(&name) => return Ok(()),
+        _ => {}
+    }
+
+    let inner_style = if node.has_tag_name(STYLE_TAG) {
+        char_style(&node, style)
+    } else {
+        style
+    };
+    for child in node.children() {
+        walk(child, inner_style, builder)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZEFANIA: &str = include_str!("../../../tests/fixtures/sample.zefania.xml");
+
+    #[test]
+    fn zefania_bible() {
+        let bible = Zefania.parse(ZEFANIA).unwrap();
+        let titles: Vec<&str> = bible.books.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, vec!["Psalms", "John"]);
+        assert_eq!(bible.metadata.language, Some("ENG".to_string()));
+        assert_eq!(bible.metadata.title.as_deref(), Some("Sample Bible"));
+
+        let ch = &bible.get("John").unwrap().chapters[0];
+        assert_eq!(ch.number, 3);
+        assert_eq!(
+            ch.paragraphs[0].headings,
+            vec!["The Conversation with Nicodemus"]
+        );
+        let verses = &ch.paragraphs[0].verses;
+        assert_eq!(
+            verses[0].text(),
+            "For this is the way God loved the world: by giving his one and only Son."
+        );
+        assert_eq!(verses[0].notes[0].text, "Or \"only begotten\"");
+        assert!(verses[1]
+            .contents
+            .iter()
+            .any(|s| s.style.words_of_jesus && s.text == "For God did not send his Son"));
+        assert_eq!(verses[1].notes[0].kind, NoteKind::CrossReference);
+        assert_eq!(ch.paragraphs[1].verses[0].number, VerseId::new(19));
+    }
+
+    #[test]
+    fn zefania_styles() {
+        let bible = Zefania.parse(ZEFANIA).unwrap();
+        let vs = &bible.get("Psalms").unwrap().chapters[0].paragraphs[0].verses[0];
+        assert_eq!(vs.text(), "The LORD is my shepherd, I lack nothing.");
+        assert!(vs
+            .contents
+            .iter()
+            .any(|s| s.style.divine_name && s.text == "LORD"));
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+
+use roxmltree::Node;
+use serde::{Deserialize, Serialize};
+
+use super::book::{title_from_id, Book};
+
+const LANGUAGE_CODE_TAG: &str = "languageCode";
+const NOTE_TAGS: &[&str] = &["f", "x", "fe", "note"];
+///USFX paragraphs that hold the main title (ie. `<p sfm="mt">`) and the running header, the
+///first one found wins
+const TITLE_MARKERS: &[&str] = &["mt", "mt1", "h"];
+const SFM_ATTR: &str = "sfm";
+
+const COPYRIGHT_WORDS: &[&str] = &["copyright", "©"];
+const LICENSE_WORDS: &[&str] = &["license", "licence", "creative commons", "public domain"];
+
+///What a translation says about itself
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BibleMetadata {
+    pub title: Option<String>,
+    ///the language code of the translation (ie. eng)
+    pub language: Option<String>,
+    pub copyright: Option<String>,
+    pub license: Option<String>,
+}
+
+///all the text in a node on one line without any notes
+pub(crate) fn text_of(node: &Node) -> String {
+    node.descendants()
+        .filter(|n| {
+            n.is_text()
+                && !n
+                    .ancestors()
+                    .any(|a| NOTE_TAGS.contains(&a.tag_name().name()))
+        })
+        .filter_map(|n| n.text())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+///the USFM marker of a USFX element (ie. `<p sfm="mt">` -> mt)
+fn marker<'a>(node: &Node<'a, '_>) -> &'a str {
+    node.attribute(SFM_ATTR)
+        .unwrap_or_else(|| node.tag_name().name())
+}
+
+fn mentions(text: &str, words: &[&str]) -> bool {
+    let text = text.to_lowercase();
+    words.iter().any(|w| text.contains(w))
+}
+
+pub(crate) fn non_empty(text: String) -> Option<String> {
+    Some(text).filter(|t| !t.is_empty())
+}
+
+impl BibleMetadata {
+    ///Read the language code and the identification and copyright material in the front matter
+    ///that comes before the books of the Bible
+    pub fn from_usfx(bible_doc: &roxmltree::Document) -> Self {
+        let language = bible_doc
+            .descendants()
+            .find(|n| n.has_tag_name(LANGUAGE_CODE_TAG))
+            .and_then(|n| non_empty(text_of(&n)));
+
+        //anything outside of the books of the Bible (ie. FRT, CPR or a <rem>)
+        let front_matter = bible_doc.root_element().children().filter(|n| {
+            n.is_element()
+                && !n.has_tag_name(LANGUAGE_CODE_TAG)
+                && !(Book::is_book_tag(n) && n.attribute("id").and_then(title_from_id).is_some())
+        });
+        let blocks: Vec<Node> = front_matter
+            .flat_map(|n| n.descendants())
+            .filter(|n| {
+                n.is_element()
+                    && n.children()
+                        .any(|c| c.is_text() && c.text().is_some_and(|t| !t.trim().is_empty()))
+            })
+            .collect();
+
+        let title = TITLE_MARKERS
+            .iter()
+            .find_map(|m| blocks.iter().find(|n| marker(n) == *m))
+            .and_then(|n| non_empty(text_of(n)));
+        let texts: Vec<String> = blocks.iter().map(text_of).collect();
+        let copyright = texts.iter().find(|t| mentions(t, COPYRIGHT_WORDS)).cloned();
+        let license = texts
+            .iter()
+            .find(|t| mentions(t, LICENSE_WORDS) && Some(*t) != copyright.as_ref())
+            .cloned();
+
+        Self {
+            title,
+            language,
+            copyright,
+            license,
+        }
+    }
+
+    ///fill in anything we don't know from another source (ie. the next book in a directory)
+    pub fn or(self, other: BibleMetadata) -> Self {
+        Self {
+            title: self.title.or(other.title),
+            language: self.language.or(other.language),
+            copyright: self.copyright.or(other.copyright),
+            license: self.license.or(other.license),
+        }
+    }
+
+    ///the first sentence of the copyright notice to put under a quoted passage
+    pub fn short_copyright(&self) -> Option<String> {
+        let copyright = self.copyright.as_deref()?;
+        let end = copyright
+            .find(". ")
+            .map(|i| i + 1)
+            .unwrap_or(copyright.len());
+        Some(copyright[..end].to_string())
+    }
+}
+
+impl fmt::Display for BibleMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = [
+            ("Title", &self.title),
+            ("Language", &self.language),
+            ("Copyright", &self.copyright),
+            ("License", &self.license),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                writeln!(f, "{}: {}", label, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USFX: &str = r#"<usfx>
+<languageCode>eng</languageCode>
+<book id="FRT">
+<id id="FRT">Front matter</id>
+<h>NET Bible</h>
+<p sfm="mt">The NET Bible, New English Translation</p>
+<p>Copyright © 1996-2019 by Biblical Studies Press, L.L.C. All rights reserved.<f caller="+">a note</f></p>
+<p>Used under license from the publisher.</p>
+</book>
+<book id="JHN"><c id="1"/><p><v id="1"/>In the beginning was the Word. Copyright is not here.</p></book>
+</usfx>"#;
+
+    #[test]
+    fn usfx_metadata() {
+        let doc = roxmltree::Document::parse(USFX).unwrap();
+        let metadata = BibleMetadata::from_usfx(&doc);
+
+        assert_eq!(
+            metadata.title.as_deref(),
+            Some("The NET Bible, New English Translation")
+        );
+        assert_eq!(metadata.language.as_deref(), Some("eng"));
+        assert_eq!(
+            metadata.copyright.as_deref(),
+            Some("Copyright © 1996-2019 by Biblical Studies Press, L.L.C. All rights reserved.")
+        );
+        assert_eq!(
+            metadata.license.as_deref(),
+            Some("Used under license from the publisher.")
+        );
+        assert_eq!(
+            metadata.short_copyright().as_deref(),
+            Some("Copyright © 1996-2019 by Biblical Studies Press, L.L.C.")
+        );
+    }
+
+    #[test]
+    fn display_metadata() {
+        let metadata = BibleMetadata {
+            title: Some("Sample Bible".to_string()),
+            language: Some("eng".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(metadata.to_string(), "Title: Sample Bible\nLanguage: eng\n");
+        assert_eq!(metadata.short_copyright(), None);
+    }
+}
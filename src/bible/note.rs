@@ -0,0 +1,63 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoteKind {
+    Footnote,
+    CrossReference,
+}
+
+impl fmt::Display for NoteKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NoteKind::Footnote => write!(f, "footnote"),
+            NoteKind::CrossReference => write!(f, "cross-reference"),
+        }
+    }
+}
+
+///a footnote or cross-reference attached to a verse
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    ///the caller from the source text (ie. `+`, `-` or `a`)
+    pub caller: String,
+    pub kind: NoteKind,
+    pub text: String,
+    ///byte offset into the verse contents where the note is anchored
+    pub offset: usize,
+}
+
+impl Note {
+    pub fn new(caller: &str, kind: NoteKind, text: &str, offset: usize) -> Self {
+        Self {
+            caller: caller.to_string(),
+            kind,
+            text: text.to_string(),
+            offset,
+        }
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            NoteKind::Footnote => write!(f, "{}", self.text),
+            NoteKind::CrossReference => write!(f, "({}) {}", self.kind, self.text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_note() {
+        let note = Note::new("+", NoteKind::Footnote, "Or \"only begotten\"", 10);
+        assert_eq!(note.to_string(), "Or \"only begotten\"");
+
+        let xref = Note::new("-", NoteKind::CrossReference, "Rom 5:8", 10);
+        assert_eq!(xref.to_string(), "(cross-reference) Rom 5:8");
+    }
+}
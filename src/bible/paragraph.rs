@@ -1,87 +1,73 @@
 use std::fmt;
-use std::fmt::Write as FmtWrite;
-use textwrap::{fill, termwidth, wrap_algorithms::Penalties, Options, WrapAlgorithm};
 
+use serde::{Deserialize, Serialize};
+
+use crate::bible::render::Renderer;
 use crate::bible::verse::Verse;
 
-// pub trait Ptype {
-//     fn as_any(&self) -> &dyn Any;
-// }
-
-// #[derive(Debug, Clone)]
-// pub struct Quote {
-//     pub verses: Vec<Verse>,
-// }
-
-// impl Ptype for Quote {
-//     fn as_any(&self) -> &dyn Any {
-//         self
-//     }
-// }
-
-// impl fmt::Display for Quote {
-//     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-//         let mut all_vs = String::new();
-//         for v in &self.verses {
-//             write!(&mut all_vs, "\t{} ", v.contents)?;
-//         }
-
-//         let opts =
-//             Options::new(termwidth()).wrap_algorithm(WrapAlgorithm::OptimalFit(Penalties::new()));
-//         write!(f, "{}", fill(&all_vs, opts))
-//     }
-// }
-
-#[derive(Debug, Clone)]
+///how a block of text is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BlockKind {
+    ///a normal paragraph that gets refilled to the width of the terminal
+    #[default]
+    Prose,
+    ///a single line of poetry indented by its level (ie. `q1`, `q2`, `q3`)
+    Poetry(u8),
+    ///a blank line, mostly used to break up stanzas
+    Blank,
+    ///a single item of a list indented by its level
+    ListItem(u8),
+}
+
+impl BlockKind {
+    ///lines of poetry and lists stay on their own line instead of being separated by a blank line
+    pub fn is_line(&self) -> bool {
+        !matches!(self, BlockKind::Prose)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Paragraph {
+    ///section headings (ie. "The Conversation with Nicodemus") that come right before this paragraph
+    pub headings: Vec<String>,
+    pub kind: BlockKind,
     pub verses: Vec<Verse>,
 }
 
-// impl Ptype for Paragraph {
-//     fn as_any(&self) -> &dyn Any {
-//         self
-//     }
-// }
-impl fmt::Display for Paragraph {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut all_vs = String::new();
-        for v in &self.verses {
-            if v.number == 0 {
-                write!(&mut all_vs, "\t{} ", v.contents)?;
-            } else {
-                write!(&mut all_vs, "[{}] {} ", v.number, v.contents)?;
-            }
+impl Paragraph {
+    pub fn with_kind(kind: BlockKind) -> Self {
+        Self {
+            kind,
+            ..Self::default()
         }
+    }
+}
 
-        //thanks Steve!!
-        let opts =
-            Options::new(termwidth()).wrap_algorithm(WrapAlgorithm::OptimalFit(Penalties::new()));
-        write!(f, "{}", fill(&all_vs, opts))
+impl fmt::Display for Paragraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Renderer::default().paragraph(self))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use textwrap::{fill, termwidth, wrap_algorithms::Penalties, Options, WrapAlgorithm};
 
     #[test]
     fn display_paragraph() {
-        let mut p = Paragraph { verses: Vec::new() };
+        let mut p = Paragraph::default();
 
         let opts =
             Options::new(termwidth()).wrap_algorithm(WrapAlgorithm::OptimalFit(Penalties::new()));
         let mut expected = r#"[34] "Where have you put him?" He asked. "Come and see, Lord," they answered. [35] Jesus wept."#.to_string();
         expected = fill(&expected, opts);
 
-        let j_1134 = Verse {
-            number: 34,
-            contents: r#""Where have you put him?" He asked. "Come and see, Lord," they answered."#
-                .to_string(),
-        };
-        let j_1135 = Verse {
-            number: 35,
-            contents: "Jesus wept.".to_string(),
-        };
+        let j_1134 = Verse::new(
+            34,
+            r#""Where have you put him?" He asked. "Come and see, Lord," they answered."#,
+        );
+        let j_1135 = Verse::new(35, "Jesus wept.");
         p.verses.push(j_1134);
         p.verses.push(j_1135);
 
@@ -89,4 +75,25 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn display_paragraph_with_heading() {
+        let mut p = Paragraph::default();
+        p.headings.push("The Death of Lazarus".to_string());
+        p.verses.push(Verse::new(35, "Jesus wept."));
+
+        assert_eq!(format!("{}", p), "The Death of Lazarus\n[35] Jesus wept.");
+    }
+
+    #[test]
+    fn display_poetry() {
+        let mut q1 = Paragraph::with_kind(BlockKind::Poetry(1));
+        q1.verses.push(Verse::new(1, "The LORD is my shepherd,"));
+        let mut q2 = Paragraph::with_kind(BlockKind::Poetry(2));
+        q2.verses.push(Verse::new(0, "I lack nothing."));
+
+        assert_eq!(format!("{}", q1), "  [1] The LORD is my shepherd,");
+        assert_eq!(format!("{}", q2), "    I lack nothing.");
+        assert_eq!(format!("{}", Paragraph::with_kind(BlockKind::Blank)), "");
+    }
 }
@@ -1,3 +1,6 @@
+use super::chapter::Chapter;
+use super::verse::VerseId;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Point {
     pub chpt: u32,
@@ -15,6 +18,24 @@ impl Point {
     pub fn is_empty(&self) -> bool {
         self.chpt == 0 && self.verse == 0
     }
+
+    ///is this point on the given verse, a bridged verse (ie. `17-18`) matches both 17 and 18
+    pub fn matches(&self, chpt: u32, id: &VerseId) -> bool {
+        self.chpt == chpt && id.contains(self.verse)
+    }
+}
+
+///The verses of a query cut out of a book, still in the chapters and paragraphs they're in so
+///they're laid out just like the whole book
+#[derive(Debug, Clone, Default)]
+pub struct Passage {
+    pub chapters: Vec<Chapter>,
+}
+
+impl Passage {
+    pub fn is_empty(&self) -> bool {
+        self.chapters.is_empty()
+    }
 }
 
 #[cfg(test)]
@@ -35,6 +56,16 @@ mod tests {
         let p2 = Point::new(2, 1);
         assert!(p2 > p1);
     }
+    #[test]
+    fn point_matches_bridge() {
+        let p = Point::new(3, 17);
+        assert!(p.matches(3, &VerseId::bridge(17, 18)));
+        assert!(p.matches(3, &VerseId::new(17)));
+        assert!(!p.matches(4, &VerseId::bridge(17, 18)));
+        assert!(!p.matches(3, &VerseId::bridge(15, 16)));
+        assert!(!Point::new(3, 19).matches(3, &VerseId::bridge(17, 18)));
+    }
+
     #[test]
     fn lt_point() {
         let p1 = Point::new(1, 1);
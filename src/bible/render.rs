@@ -0,0 +1,345 @@
+use std::fmt::Write as FmtWrite;
+
+use textwrap::{fill, termwidth, wrap_algorithms::Penalties, Options, WrapAlgorithm};
+
+use super::chapter::Chapter;
+use super::note::Note;
+use super::paragraph::{BlockKind, Paragraph};
+use super::passage::Passage;
+use super::span::Style;
+use super::verse::Verse;
+
+const RED: &str = "\x1b[31m";
+const ITALIC: &str = "\x1b[3m";
+const RESET: &str = "\x1b[0m";
+
+///Turns verses, paragraphs and chapters into wrapped text for the terminal.
+///
+///When notes are shown each footnote and cross-reference gets a numbered marker (ie. `{1}`) in
+///the text and is collected so that it can be printed after the passage with `take_notes`.
+///When styled the words of Jesus are red, the divine name is in caps and added words are in
+///italics using ANSI escape codes, so only turn it on for a terminal.
+#[derive(Debug, Default)]
+pub struct Renderer {
+    pub show_notes: bool,
+    pub styled: bool,
+    notes: Vec<Note>,
+}
+
+fn wrap(text: &str) -> String {
+    //thanks Steve!!
+    let opts =
+        Options::new(termwidth()).wrap_algorithm(WrapAlgorithm::OptimalFit(Penalties::new()));
+    fill(text, opts)
+}
+
+///wrap a single line keeping it indented, long lines hang a little further in
+fn wrap_line(text: &str, level: u8) -> String {
+    let indent = "  ".repeat(level.into());
+    let hanging = format!("{}  ", indent);
+    let opts = Options::new(termwidth())
+        .initial_indent(&indent)
+        .subsequent_indent(&hanging);
+    fill(text, opts)
+}
+
+impl Renderer {
+    pub fn new(show_notes: bool, styled: bool) -> Self {
+        Self {
+            show_notes,
+            styled,
+            notes: Vec::new(),
+        }
+    }
+
+    fn style(&self, text: &str, style: Style) -> String {
+        if !self.styled || style.is_plain() || text.is_empty() {
+            return text.to_string();
+        }
+
+        let mut styled = String::new();
+        if style.words_of_jesus {
+            styled.push_str(RED);
+        }
+        if style.added {
+            styled.push_str(ITALIC);
+        }
+        if style.divine_name {
+            styled.push_str(&text.to_uppercase());
+        } else {
+            styled.push_str(text);
+        }
+        if style.words_of_jesus || style.added {
+            styled.push_str(RESET);
+        }
+        styled
+    }
+
+    fn mark(&mut self, out: &mut String, note: &Note) {
+        self.notes.push(note.clone());
+        // writing to a String can't fail
+        let _ = write!(out, "{{{}}}", self.notes.len());
+    }
+
+    ///the verse contents with a marker at the anchor of each note
+    fn contents(&mut self, vs: &Verse) -> String {
+        let mut out = String::new();
+        let no_notes = Vec::new();
+        let notes = if self.show_notes {
+            &vs.notes
+        } else {
+            &no_notes
+        };
+        let mut notes = notes.iter().peekable();
+
+        //the offset into the plain text of the verse where the current span starts
+        let mut offset = 0;
+        for span in &vs.contents {
+            let mut rest = span.text.as_str();
+            while let Some(note) = notes.next_if(|n| n.offset <= offset + rest.len()) {
+                let mut at = note.offset.saturating_sub(offset);
+                while !rest.is_char_boundary(at) {
+                    at += 1;
+                }
+                out.push_str(&self.style(&rest[..at], span.style));
+                offset += at;
+                rest = &rest[at..];
+                self.mark(&mut out, note);
+            }
+            out.push_str(&self.style(rest, span.style));
+            offset += rest.len();
+        }
+
+        //anything anchored past the end of the text goes at the very end
+        for note in notes {
+            self.mark(&mut out, note);
+        }
+        out
+    }
+
+    pub fn verse(&mut self, vs: &Verse) -> String {
+        let contents = self.contents(vs);
+        let v: String = if vs.number.is_partial() {
+            contents
+        } else {
+            format!("[{}] {}", vs.number, contents)
+        };
+        wrap(&v)
+    }
+
+    pub fn paragraph(&mut self, pgh: &Paragraph) -> String {
+        let mut headings = String::new();
+        for heading in &pgh.headings {
+            headings.push_str(&wrap(heading));
+            headings.push('\n');
+        }
+
+        let mut all_vs = String::new();
+        for v in &pgh.verses {
+            let contents = self.contents(v);
+            if !v.number.is_partial() {
+                let _ = write!(&mut all_vs, "[{}] {} ", v.number, contents);
+            } else if pgh.kind == BlockKind::Prose {
+                let _ = write!(&mut all_vs, "\t{} ", contents);
+            } else {
+                let _ = write!(&mut all_vs, "{} ", contents);
+            }
+        }
+
+        let body = match pgh.kind {
+            BlockKind::Prose => wrap(&all_vs),
+            BlockKind::Poetry(level) | BlockKind::ListItem(level) => {
+                wrap_line(all_vs.trim(), level)
+            }
+            BlockKind::Blank => String::new(),
+        };
+        headings + &body
+    }
+
+    pub fn chapter(&mut self, ch: &Chapter) -> String {
+        format!("CHAPTER_{}\n{}", ch.number, self.paragraphs(&ch.paragraphs))
+    }
+
+    ///a passage laid out like its chapters, with the number of each one after the first
+    pub fn passage(&mut self, passage: &Passage) -> String {
+        let mut out = String::new();
+        for (i, ch) in passage.chapters.iter().enumerate() {
+            if i == 0 {
+                out.push_str(&self.paragraphs(&ch.paragraphs));
+            } else {
+                out.push_str(&self.chapter(ch));
+            }
+        }
+        out
+    }
+
+    fn paragraphs(&mut self, paragraphs: &[Paragraph]) -> String {
+        let mut out = String::new();
+        let mut pghs = paragraphs.iter().peekable();
+        while let Some(p) = pghs.next() {
+            out.push_str(&self.paragraph(p));
+            //keep the lines of a stanza together
+            match pghs.peek() {
+                Some(next) if p.kind.is_line() && next.kind.is_line() => out.push('\n'),
+                _ => out.push_str("\n\n"),
+            }
+        }
+        out
+    }
+
+    ///the notes collected since the last call, numbered to match their markers
+    pub fn take_notes(&mut self) -> Option<String> {
+        if self.notes.is_empty() {
+            return None;
+        }
+        let mut out = String::from("Notes:\n");
+        for (i, note) in self.notes.drain(..).enumerate() {
+            out.push_str(&wrap(&format!("{{{}}} {}", i + 1, note)));
+            out.push('\n');
+        }
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bible::note::NoteKind;
+
+    fn noted_verse() -> Verse {
+        let mut vs = Verse::new(
+            16,
+            "For this is the way God loved the world: by giving his one and only Son.",
+        );
+        vs.notes.push(Note::new(
+            "+",
+            NoteKind::Footnote,
+            "Or \"For God so loved the world\"",
+            40,
+        ));
+        vs.notes
+            .push(Note::new("-", NoteKind::CrossReference, "Rom 5:8", 72));
+        vs
+    }
+
+    #[test]
+    fn render_without_notes() {
+        let mut renderer = Renderer::new(false, false);
+        let result = renderer.verse(&noted_verse());
+        assert_eq!(
+            result,
+            wrap("[16] For this is the way God loved the world: by giving his one and only Son.")
+        );
+        assert!(renderer.take_notes().is_none());
+    }
+
+    #[test]
+    fn render_styles() {
+        let wj = Style {
+            words_of_jesus: true,
+            ..Style::default()
+        };
+        let nd = Style {
+            divine_name: true,
+            ..Style::default()
+        };
+        let mut vs = Verse::new(4, "Jesus answered, ");
+        vs.push_text("\"Worship the ", wj);
+        vs.push_text(
+            "Lord",
+            Style {
+                divine_name: true,
+                ..wj
+            },
+        );
+        vs.push_text(".\"", wj);
+        vs.push_text(" The ", Style::default());
+        vs.push_text("Lord", nd);
+        vs.notes
+            .push(Note::new("+", NoteKind::Footnote, "Deut 6:13", 35));
+
+        let mut plain = Renderer::new(true, false);
+        assert_eq!(
+            plain.verse(&vs),
+            wrap("[4] Jesus answered, \"Worship the Lord.\"{1} The Lord")
+        );
+
+        let mut styled = Renderer::new(true, true);
+        assert_eq!(
+            styled.verse(&vs),
+            wrap("[4] Jesus answered, \x1b[31m\"Worship the \x1b[0m\x1b[31mLORD\x1b[0m\x1b[31m.\"\x1b[0m{1} The LORD")
+        );
+    }
+
+    #[test]
+    fn render_stanzas() {
+        let mut ch = Chapter {
+            number: 23,
+            paragraphs: Vec::new(),
+        };
+        let lines = [
+            (
+                BlockKind::Poetry(1),
+                Verse::new(1, "The LORD is my shepherd,"),
+            ),
+            (BlockKind::Poetry(2), Verse::new(0, "I lack nothing.")),
+            (BlockKind::Blank, Verse::new(0, "")),
+            (
+                BlockKind::Poetry(1),
+                Verse::new(2, "He takes me to lush pastures,"),
+            ),
+            (BlockKind::Prose, Verse::new(3, "He restores my strength.")),
+        ];
+        for (kind, vs) in lines {
+            let mut p = Paragraph::with_kind(kind);
+            if kind != BlockKind::Blank {
+                p.verses.push(vs);
+            }
+            ch.paragraphs.push(p);
+        }
+
+        let expected = "CHAPTER_23\n  [1] The LORD is my shepherd,\n    I lack nothing.\n\n  [2] He takes me to lush pastures,\n\n[3] He restores my strength.\n\n";
+        assert_eq!(Renderer::default().chapter(&ch), expected);
+    }
+
+    #[test]
+    fn render_passage() {
+        let chapter = |number: u32, verses: &[(u32, &str)]| {
+            let mut p = Paragraph::default();
+            for (n, text) in verses {
+                p.verses.push(Verse::new(*n, text));
+            }
+            Chapter {
+                number,
+                paragraphs: vec![p],
+            }
+        };
+        let passage = Passage {
+            chapters: vec![
+                chapter(2, &[(25, "He did not need anyone to testify.")]),
+                chapter(3, &[(1, "Now a man of the Pharisees.")]),
+            ],
+        };
+
+        let expected = "[25] He did not need anyone to testify.\n\nCHAPTER_3\n[1] Now a man of the Pharisees.\n\n";
+        assert_eq!(Renderer::default().passage(&passage), expected);
+    }
+
+    #[test]
+    fn render_with_notes() {
+        let mut renderer = Renderer::new(true, false);
+        let result = renderer.verse(&noted_verse());
+        assert_eq!(
+            result,
+            wrap("[16] For this is the way God loved the world:{1} by giving his one and only Son.{2}")
+        );
+
+        let notes = renderer.take_notes().unwrap();
+        assert_eq!(
+            notes,
+            "Notes:\n{1} Or \"For God so loved the world\"\n{2} (cross-reference) Rom 5:8\n"
+        );
+        //notes are only handed out once
+        assert!(renderer.take_notes().is_none());
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+///character styles from the source text, they can be nested so more than one can be on at once
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Style {
+    ///words of Jesus (ie. `<wj>`) for red-letter printing
+    pub words_of_jesus: bool,
+    ///the name of God (ie. `<nd>`) usually printed in small caps as LORD
+    pub divine_name: bool,
+    ///words added by the translators (ie. `<add>`) usually printed in italics
+    pub added: bool,
+}
+
+impl Style {
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+}
+
+///a run of verse text that all has the same style
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Span {
+    pub fn new(text: &str, style: Style) -> Self {
+        Self {
+            text: text.to_string(),
+            style,
+        }
+    }
+}
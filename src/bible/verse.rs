@@ -1,32 +1,148 @@
 use std::fmt;
+use std::str::FromStr;
 
-use textwrap::{fill, termwidth, wrap_algorithms::Penalties, Options, WrapAlgorithm};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+use super::note::Note;
+use super::render::Renderer;
+use super::span::{Span, Style};
+
+///The number of a verse as it is in the source text. Some translations join verses together
+///into a bridge (ie. `17-18`) or split one up into segments (ie. `4a`, `4b`).
+///A start of 0 is the rest of a verse carried over from the paragraph before.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct VerseId {
+    pub start: u32,
+    pub end: u32,
+    pub segment: Option<char>,
+}
+
+impl VerseId {
+    pub fn new(number: u32) -> Self {
+        Self {
+            start: number,
+            end: number,
+            segment: None,
+        }
+    }
+
+    pub fn bridge(start: u32, end: u32) -> Self {
+        Self {
+            start,
+            end,
+            segment: None,
+        }
+    }
+
+    ///the rest of a verse that started in an earlier paragraph
+    pub fn is_partial(&self) -> bool {
+        self.start == 0
+    }
+
+    ///does this verse hold the given verse number, ie. `17-18` holds 17 and 18 and `4a` holds 4
+    pub fn contains(&self, number: u32) -> bool {
+        !self.is_partial() && self.start <= number && number <= self.end
+    }
+}
+
+impl From<u32> for VerseId {
+    fn from(number: u32) -> Self {
+        VerseId::new(number)
+    }
+}
+
+impl FromStr for VerseId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //a trailing letter is a segment of the verse (ie. 4a)
+        let (numbers, segment) = match s.trim().char_indices().last() {
+            Some((i, c)) if c.is_ascii_alphabetic() => (&s.trim()[..i], Some(c)),
+            _ => (s.trim(), None),
+        };
+
+        let mut bounds = numbers.splitn(2, ['-', '\u{2013}']);
+        let start = bounds
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .parse::<u32>()
+            .context(format!("{} is not a valid verse number", s))?;
+        let end = match bounds.next() {
+            Some(end) => end
+                .trim()
+                .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                .parse::<u32>()
+                .context(format!("{} is not a valid verse bridge", s))?,
+            None => start,
+        };
+
+        if end < start {
+            anyhow::bail!("the verse bridge {} runs backwards", s);
+        }
+
+        Ok(Self {
+            start,
+            end,
+            segment,
+        })
+    }
+}
+
+impl fmt::Display for VerseId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.start)?;
+        if self.end != self.start {
+            write!(f, "-{}", self.end)?;
+        }
+        if let Some(segment) = self.segment {
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Verse {
-    pub number: u32,
-    pub contents: String,
+    pub number: VerseId,
+    pub contents: Vec<Span>,
+    pub notes: Vec<Note>,
 }
 
 impl Verse {
     pub fn new(vs_num: u32, content: &str) -> Self {
-        Self {
-            number: vs_num,
-            contents: content.to_string(),
+        let mut vs = Self {
+            number: VerseId::new(vs_num),
+            contents: Vec::new(),
+            notes: Vec::new(),
+        };
+        vs.push_text(content, Style::default());
+        vs
+    }
+
+    ///add text to the end of the verse, joining it to the last span if the style matches
+    pub fn push_text(&mut self, text: &str, style: Style) {
+        if text.is_empty() {
+            return;
+        }
+        match self.contents.last_mut() {
+            Some(last) if last.style == style => last.text.push_str(text),
+            _ => self.contents.push(Span::new(text, style)),
         }
     }
+
+    ///the text of the verse without any styling
+    pub fn text(&self) -> String {
+        self.contents.iter().map(|s| s.text.as_str()).collect()
+    }
 }
 
 impl fmt::Display for Verse {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let v: String = if self.number == 0 {
-            self.contents.to_string()
-        } else {
-            format!("[{}] {}", self.number, self.contents)
-        };
-        let opts =
-            Options::new(termwidth()).wrap_algorithm(WrapAlgorithm::OptimalFit(Penalties::new()));
-        write!(f, "{}", fill(&v, opts))
+        write!(f, "{}", Renderer::default().verse(self))
     }
 }
 
@@ -36,16 +152,79 @@ mod tests {
 
     #[test]
     fn display_verse() {
-        let v = Verse {
-            number: 35,
-            contents: "Jesus wept.".to_string(),
-        };
+        let v = Verse::new(35, "Jesus wept.");
         assert_eq!(format!("{}", v), "[35] Jesus wept.");
 
-        let v2 = Verse {
-            number: 0,
-            contents: "Because you have done this".to_string(),
-        };
+        let v2 = Verse::new(0, "Because you have done this");
         assert_eq!(format!("{}", v2), "Because you have done this");
     }
+
+    #[test]
+    fn parse_verse_id() {
+        assert_eq!("16".parse::<VerseId>().unwrap(), VerseId::new(16));
+        assert_eq!("17-18".parse::<VerseId>().unwrap(), VerseId::bridge(17, 18));
+        assert_eq!(
+            "4a".parse::<VerseId>().unwrap(),
+            VerseId {
+                start: 4,
+                end: 4,
+                segment: Some('a'),
+            }
+        );
+        assert_eq!(
+            "3-4b".parse::<VerseId>().unwrap(),
+            VerseId {
+                start: 3,
+                end: 4,
+                segment: Some('b'),
+            }
+        );
+        assert!("18-17".parse::<VerseId>().is_err());
+        assert!("abc".parse::<VerseId>().is_err());
+        assert!("".parse::<VerseId>().is_err());
+    }
+
+    #[test]
+    fn verse_id_contains() {
+        let bridge = VerseId::bridge(17, 18);
+        assert!(bridge.contains(17));
+        assert!(bridge.contains(18));
+        assert!(!bridge.contains(19));
+        assert!("4a".parse::<VerseId>().unwrap().contains(4));
+        assert!(!VerseId::new(0).contains(0));
+    }
+
+    #[test]
+    fn display_verse_id() {
+        assert_eq!(VerseId::bridge(17, 18).to_string(), "17-18");
+        assert_eq!("4a".parse::<VerseId>().unwrap().to_string(), "4a");
+        assert_eq!(
+            format!(
+                "{}",
+                Verse {
+                    number: VerseId::bridge(17, 18),
+                    ..Verse::new(0, "For God did not send his Son into the world.")
+                }
+            ),
+            "[17-18] For God did not send his Son into the world."
+        );
+    }
+
+    #[test]
+    fn push_text_joins_spans() {
+        let wj = Style {
+            words_of_jesus: true,
+            ..Style::default()
+        };
+        let mut v = Verse::new(3, "Jesus replied, ");
+        v.push_text("\"I tell you the solemn truth, ", wj);
+        v.push_text("unless a person is born from above", wj);
+        v.push_text("", Style::default());
+
+        assert_eq!(v.contents.len(), 2);
+        assert_eq!(
+            v.text(),
+            "Jesus replied, \"I tell you the solemn truth, unless a person is born from above"
+        );
+    }
 }
@@ -0,0 +1,240 @@
+use std::fs;
+use std::path::{Component, Path};
+
+use anyhow::Context as _;
+
+use crate::fetch::Fetcher;
+use crate::registry::{remove_from_manifest, Registry, Translation};
+use crate::storage::{path_in, save_into, update_into};
+
+///is a translation on this computer yet
+pub fn is_installed(data_dir: &Path, registry: &Registry, translation: &Translation) -> bool {
+    path_in(data_dir, translation, registry).exists()
+}
+
+///a line for each translation in the catalog saying which are installed
+pub fn list(data_dir: &Path, registry: &Registry) -> Vec<String> {
+    let id_width = registry
+        .translations()
+        .iter()
+        .map(|t| t.id.len())
+        .max()
+        .unwrap_or_default();
+    registry
+        .translations()
+        .iter()
+        .map(|t| {
+            let installed = if is_installed(data_dir, registry, t) {
+                " (installed)"
+            } else {
+                ""
+            };
+            format!(
+                "{:<width$}  {:<10} {}{}",
+                t.id,
+                registry.language_name(&t.language),
+                t.name,
+                installed,
+                width = id_width
+            )
+        })
+        .collect()
+}
+
+///Download a translation in the catalog into the data dir
+pub fn install<'a>(
+    data_dir: &Path,
+    registry: &'a Registry,
+    fetcher: &dyn Fetcher,
+    id: &str,
+) -> anyhow::Result<&'a Translation> {
+    let translation = registry.get(id)?;
+    anyhow::ensure!(
+        !translation.is_local(),
+        "{} is already on this computer at {}",
+        translation.id,
+        translation.source
+    );
+    save_into(data_dir, translation, registry, fetcher)?;
+    Ok(translation)
+}
+
+///is a directory right inside one of the language directories in the data dir
+fn is_in_data_dir(data_dir: &Path, dir: &Path) -> bool {
+    dir.strip_prefix(data_dir).is_ok_and(|rest| {
+        let parts: Vec<Component> = rest.components().collect();
+        parts.len() == 2 && parts.iter().all(|c| matches!(c, Component::Normal(_)))
+    })
+}
+
+///Delete a translation from the data dir, one that was imported is taken out of the user's
+///manifest as well
+pub fn remove(
+    data_dir: &Path,
+    registry: &Registry,
+    manifest: &Path,
+    id: &str,
+) -> anyhow::Result<()> {
+    let translation = registry.get(id)?;
+    let version_dir = registry.translation_dir(data_dir, translation);
+    anyhow::ensure!(
+        is_in_data_dir(data_dir, &version_dir),
+        "{} is not in {}, it won't be removed",
+        version_dir.display(),
+        data_dir.display()
+    );
+    let had_files = version_dir.exists();
+    if had_files {
+        fs::remove_dir_all(&version_dir)
+            .context(format!("cannot remove {}", version_dir.display()))?;
+    }
+    //a Bible the user pointed at somewhere else is left where it is
+    let had_entry = translation.is_local() && remove_from_manifest(manifest, &translation.id)?;
+    anyhow::ensure!(had_files || had_entry, "{} isn't installed", translation.id);
+    Ok(())
+}
+
+///Download installed translations again and replace the ones that have changed, all of them
+///when no id is given. Returns the ids of the ones that were replaced.
+pub fn update(
+    data_dir: &Path,
+    registry: &Registry,
+    fetcher: &dyn Fetcher,
+    id: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let translations: Vec<&Translation> = match id {
+        Some(id) => {
+            let translation = registry.get(id)?;
+            anyhow::ensure!(
+                !translation.is_local(),
+                "{} is on this computer, there is nowhere to update it from",
+                translation.id
+            );
+            anyhow::ensure!(
+                is_installed(data_dir, registry, translation),
+                "{} isn't installed, install it with `kyro bibles install {}`",
+                translation.id,
+                translation.id
+            );
+            vec![translation]
+        }
+        None => registry
+            .translations()
+            .iter()
+            .filter(|t| !t.is_local() && is_installed(data_dir, registry, t))
+            .collect(),
+    };
+
+    let mut updated = Vec::new();
+    for translation in translations {
+        if update_into(data_dir, translation, registry, fetcher)
+            .context(format!("cannot update {}", translation.id))?
+        {
+            updated.push(translation.id.clone());
+        }
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::bible::format;
+    use crate::fetch::FileFetcher;
+
+    const SAMPLE: &str = include_str!("../tests/fixtures/sample.osis.xml");
+
+    fn write_zip(path: &Path, contents: &str) -> anyhow::Result<()> {
+        let mut zip = zip::ZipWriter::new(File::create(path)?);
+        zip.start_file("sample.osis.xml", Default::default())?;
+        zip.write_all(contents.as_bytes())?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    ///a catalog with a zip on this computer standing in for ebible.org
+    fn catalog(dir: &Path) -> anyhow::Result<(PathBuf, Registry)> {
+        let zip_path = dir.join("sample.zip");
+        write_zip(&zip_path, SAMPLE)?;
+        let catalog_path = dir.join("catalog.toml");
+        fs::write(
+            &catalog_path,
+            format!(
+                r#"
+[languages]
+en = "English"
+
+[[translation]]
+id = "sample"
+language = "en"
+name = "Sample Bible"
+format = "osis"
+source = "file://{}"
+"#,
+                zip_path.display()
+            ),
+        )?;
+        Ok((zip_path, Registry::with_catalog(&catalog_path)?))
+    }
+
+    #[test]
+    fn install_update_and_remove() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_dir = dir.path().join("data");
+        let manifest = dir.path().join("translations.toml");
+        let (zip_path, registry) = catalog(dir.path())?;
+        let sample = registry.get("sample")?;
+
+        assert!(!is_installed(&data_dir, &registry, sample));
+        assert!(update(&data_dir, &registry, &FileFetcher, Some("sample")).is_err());
+        install(&data_dir, &registry, &FileFetcher, "sample")?;
+        assert!(is_installed(&data_dir, &registry, sample));
+        assert!(list(&data_dir, &registry)
+            .iter()
+            .any(|l| l.starts_with("sample") && l.ends_with("Sample Bible (installed)")));
+
+        assert!(update(&data_dir, &registry, &FileFetcher, None)?.is_empty());
+        write_zip(
+            &zip_path,
+            &SAMPLE.replace("the way God loved", "how God loved"),
+        )?;
+        assert_eq!(
+            update(&data_dir, &registry, &FileFetcher, None)?,
+            vec!["sample"]
+        );
+        let source = path_in(&data_dir, sample, &registry);
+        assert!(fs::read_to_string(&source)?.contains("how God loved"));
+        assert!(format::read_bible(&source).is_ok());
+
+        remove(&data_dir, &registry, &manifest, "sample")?;
+        assert!(!is_installed(&data_dir, &registry, sample));
+        assert!(remove(&data_dir, &registry, &manifest, "sample").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn only_remove_from_the_data_dir() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let data_dir = dir.path().join("data");
+        let manifest = dir.path().join("translations.toml");
+        let (_, mut registry) = catalog(dir.path())?;
+        install(&data_dir, &registry, &FileFetcher, "sample")?;
+
+        //not something a manifest can have in it but a registry can still be handed one
+        let mut escape = registry.get("sample")?.clone();
+        escape.id = "..".to_string();
+        registry.add(escape);
+        assert!(remove(&data_dir, &registry, &manifest, "..").is_err());
+        assert!(is_installed(&data_dir, &registry, registry.get("sample")?));
+
+        assert!(is_in_data_dir(&data_dir, &data_dir.join("English/sample")));
+        assert!(!is_in_data_dir(&data_dir, &data_dir.join("English")));
+        assert!(!is_in_data_dir(&data_dir, &data_dir.join("English/./x/..")));
+        assert!(!is_in_data_dir(&data_dir, dir.path()));
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context as _;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::bible::{format, Bible};
+
+const MAGIC: [u8; 4] = *b"KYRO";
+///bump this whenever the layout of the cache, the Bible model or the titles of the books change
+const FORMAT_VERSION: u32 = 3;
+const CACHE_EXT: &str = "kyro";
+
+///Written at the start of every cache file so we can tell if it still matches its source
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheHeader {
+    magic: [u8; 4],
+    format_version: u32,
+    kyro_version: String,
+    ///sha256 of the source file in hex
+    source_checksum: String,
+    source_len: u64,
+    ///seconds since the epoch
+    source_modified: u64,
+}
+
+impl CacheHeader {
+    fn new(source: &Path) -> anyhow::Result<Self> {
+        let files = format::source_files(source)?;
+        let (source_len, source_modified) = file_stamp(&files)?;
+        Ok(Self {
+            magic: MAGIC,
+            format_version: FORMAT_VERSION,
+            kyro_version: env!("CARGO_PKG_VERSION").to_string(),
+            source_checksum: checksum_files(&files)?,
+            source_len,
+            source_modified,
+        })
+    }
+
+    ///was the cache made by this version of kyro from this exact source
+    fn is_fresh(&self, source: &Path) -> anyhow::Result<bool> {
+        if self.magic != MAGIC
+            || self.format_version != FORMAT_VERSION
+            || self.kyro_version != env!("CARGO_PKG_VERSION")
+        {
+            return Ok(false);
+        }
+
+        //hashing the source takes longer than loading the cache so only do it when the files
+        //look like they have been touched
+        let files = format::source_files(source)?;
+        let (len, modified) = file_stamp(&files)?;
+        if len != self.source_len {
+            return Ok(false);
+        }
+        if modified == self.source_modified {
+            return Ok(true);
+        }
+        Ok(checksum_files(&files)? == self.source_checksum)
+    }
+}
+
+///the total size and the latest modification time of the source files
+fn file_stamp(files: &[PathBuf]) -> anyhow::Result<(u64, u64)> {
+    let mut len = 0;
+    let mut latest = 0;
+    for path in files {
+        let meta = fs::metadata(path).context(format!("cannot read {}", path.display()))?;
+        let modified = meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        len += meta.len();
+        latest = latest.max(modified);
+    }
+    Ok((len, latest))
+}
+
+///the sha256 of a few files one after the other in hex
+pub(crate) fn checksum_files(files: &[PathBuf]) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0; 64 * 1024];
+    for path in files {
+        let mut file = File::open(path).context(format!("cannot open {}", path.display()))?;
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+///the cache lives right beside its source (ie. engnet_usfx.xml -> engnet_usfx.kyro) or inside of
+///a directory of USFM files
+pub fn cache_path(source: &Path) -> PathBuf {
+    if source.is_dir() {
+        source.join("bible").with_extension(CACHE_EXT)
+    } else {
+        source.with_extension(CACHE_EXT)
+    }
+}
+
+fn compile(source: &Path) -> anyhow::Result<(CacheHeader, Bible)> {
+    let header = CacheHeader::new(source)?;
+    Ok((header, format::read_bible(source)?))
+}
+
+fn write_cache(source: &Path, header: &CacheHeader, bible: &Bible) -> anyhow::Result<()> {
+    //write to the side and then move it into place so a half written cache is never read
+    let cache = cache_path(source);
+    let tmp = cache.with_extension("kyro.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp).context("cannot create the cache")?);
+    bincode::serialize_into(&mut writer, header)?;
+    bincode::serialize_into(&mut writer, bible)?;
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&tmp, &cache).context("cannot move the cache into place")?;
+    Ok(())
+}
+
+///parse the source and write the compiled Bible next to it
+pub fn build_cache(source: &Path) -> anyhow::Result<Bible> {
+    let (header, bible) = compile(source)?;
+    write_cache(source, &header, &bible)?;
+    Ok(bible)
+}
+
+///read the cache if it's still good
+fn read_cache(source: &Path) -> anyhow::Result<Option<Bible>> {
+    let cache = cache_path(source);
+    if !cache.is_file() {
+        return Ok(None);
+    }
+
+    let file = File::open(&cache)?;
+    // SAFETY: the cache is only ever replaced by renaming a new file over it so the mapped file
+    // isn't changed underneath us
+    let mmap = unsafe { Mmap::map(&file)? };
+    let mut bytes: &[u8] = &mmap;
+
+    let header: CacheHeader = match bincode::deserialize_from(&mut bytes) {
+        Ok(header) => header,
+        Err(_) => return Ok(None),
+    };
+    if !header.is_fresh(source)? {
+        return Ok(None);
+    }
+    Ok(bincode::deserialize_from(&mut bytes).ok())
+}
+
+///Load a Bible from the cache, compiling it from the source first if the cache is missing or
+///out of date
+pub fn load_bible(source: &Path) -> anyhow::Result<Bible> {
+    if let Some(bible) = read_cache(source)? {
+        return Ok(bible);
+    }
+    let (header, bible) = compile(source)?;
+    //not being able to write the cache (ie. a read-only data dir) only costs us speed
+    let _ = write_cache(source, &header, &bible);
+    Ok(bible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USFX: &str = r#"<usfx><languageCode>eng</languageCode><book id="JHN"><c id="11"/><p><v id="35"/>Jesus wept.</p></book></usfx>"#;
+
+    fn write_source(dir: &Path, contents: &str) -> PathBuf {
+        let source = dir.join("engnet_usfx.xml");
+        fs::write(&source, contents).unwrap();
+        source
+    }
+
+    #[test]
+    fn cache_is_built_and_reused() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = write_source(dir.path(), USFX);
+
+        let built = load_bible(&source)?;
+        assert!(cache_path(&source).is_file());
+
+        let cached = read_cache(&source)?.expect("the cache should be fresh");
+        assert_eq!(cached.books.len(), built.books.len());
+        assert_eq!(
+            cached.get("John").unwrap().chapters[0].paragraphs[0].verses[0].text(),
+            "Jesus wept."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cache_rebuilds_when_source_changes() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = write_source(dir.path(), USFX);
+        load_bible(&source)?;
+
+        write_source(
+            dir.path(),
+            &USFX.replace("Jesus wept.", "Jesus wept loudly."),
+        );
+        assert!(read_cache(&source)?.is_none());
+
+        let bible = load_bible(&source)?;
+        assert_eq!(
+            bible.get("John").unwrap().chapters[0].paragraphs[0].verses[0].text(),
+            "Jesus wept loudly."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stale_format_is_not_fresh() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = write_source(dir.path(), USFX);
+
+        let mut header = CacheHeader::new(&source)?;
+        assert!(header.is_fresh(&source)?);
+        header.format_version = FORMAT_VERSION + 1;
+        assert!(!header.is_fresh(&source)?);
+
+        let mut header = CacheHeader::new(&source)?;
+        header.kyro_version = "0.0.0".to_string();
+        assert!(!header.is_fresh(&source)?);
+        Ok(())
+    }
+
+    #[test]
+    fn usfm_dir_is_cached() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/usfm");
+        for file in format::source_files(&fixtures)? {
+            fs::copy(&file, dir.path().join(file.file_name().unwrap()))?;
+        }
+
+        assert_eq!(load_bible(dir.path())?.books.len(), 2);
+        assert!(cache_path(dir.path()).is_file());
+        assert!(read_cache(dir.path())?.is_some());
+
+        //a book added to the draft means it needs to be read again
+        fs::write(
+            dir.path().join("45ROM.usfm"),
+            "\\id ROM\n\\c 1\n\\p\n\\v 1 Paul",
+        )?;
+        assert!(read_cache(dir.path())?.is_none());
+        assert_eq!(load_bible(dir.path())?.books.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn garbage_cache_is_ignored() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = write_source(dir.path(), USFX);
+        fs::write(cache_path(&source), b"not a cache")?;
+
+        assert!(read_cache(&source)?.is_none());
+        assert_eq!(load_bible(&source)?.books.len(), 1);
+        Ok(())
+    }
+}
@@ -1,50 +1,243 @@
 pub mod query;
+pub mod reference;
 pub mod show;
 
+use std::path::PathBuf;
+
+use crate::bible::render::Renderer;
+use crate::bibles;
+use crate::fetch::Fetchers;
+use crate::import::{import_bible, ImportOpts};
+use crate::registry::{user_manifest_path, Registry};
+use crate::storage::get_data_dir;
 use crate::Config;
-use crate::Query;
+use anyhow::Context as _;
 use structopt::StructOpt;
 
+use self::reference::Reference;
 use self::show::gen_seed_from_date;
 
+#[derive(StructOpt, Debug, Default)]
+pub struct NoteOpts {
+    /// Show footnotes and cross-references after the passage
+    #[structopt(long, overrides_with = "no-notes")]
+    pub notes: bool,
+    /// Hide footnotes and cross-references
+    #[structopt(long, overrides_with = "notes")]
+    pub no_notes: bool,
+}
+
+impl NoteOpts {
+    ///the command line flags win over the config
+    pub fn show_notes(&self, config: &Config) -> bool {
+        if self.notes {
+            true
+        } else if self.no_notes {
+            false
+        } else {
+            config.notes
+        }
+    }
+}
+
 #[derive(StructOpt)]
 #[structopt(name = "kyro", about = "Read the Bible on the commandline")]
+pub struct Opts {
+    /// The Bible to read from by its id (ie. net), instead of the one in the config
+    #[structopt(short, long, global = true)]
+    pub bible: Option<String>,
+    /// The language to read in (ie. french), the first Bible in it is used without --bible
+    #[structopt(short, long, global = true)]
+    pub lang: Option<String>,
+    #[structopt(subcommand)]
+    pub cmd: Command,
+}
+
+impl Opts {
+    pub fn run(&self, config: &Config, registry: &Registry) -> anyhow::Result<()> {
+        self.cmd.run(
+            config,
+            registry,
+            self.bible.as_deref(),
+            self.lang.as_deref(),
+        )
+    }
+}
+
+#[derive(StructOpt)]
 pub enum Command {
     /// Search for a passage and print to console
-    Search { book: String, chapter_verse: String },
+    Search {
+        /// The passage to find (ie. "1 Cor 13:4-7"), any common name or abbreviation of a book
+        /// works and several can be listed (ie. "John 3:16,18; Rom 8:28-39")
+        #[structopt(required = true)]
+        reference: Vec<String>,
+        #[structopt(flatten)]
+        note_opts: NoteOpts,
+        /// Put the copyright line of the translation under the passage
+        #[structopt(long)]
+        copyright: bool,
+    },
     /// Start reading the bible with `less`
     Read {
-        book: String,
-        chapter: Option<String>,
+        /// The book to read and optionally the chapter to start at (ie. John 3)
+        #[structopt(required = true)]
+        reference: Vec<String>,
+        #[structopt(flatten)]
+        note_opts: NoteOpts,
     },
     /// Display the verse of the day
     Today,
+    /// Show the title, language, copyright and license of the Bible
+    Info,
+    /// Add a Bible from a file, a directory with a file for each book or a zip of either
+    Import {
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+        /// The name to pick it with using --bible, the file name by default
+        #[structopt(long)]
+        name: Option<String>,
+        /// The code of the language to file it under (ie. eng), worked out from the Bible by
+        /// default
+        #[structopt(long)]
+        language: Option<String>,
+    },
+    /// List the Bibles kyro can get, or install, remove and update them
+    Bibles {
+        /// A manifest of translations to use instead of the one kyro comes with
+        #[structopt(long, parse(from_os_str))]
+        catalog: Option<PathBuf>,
+        #[structopt(subcommand)]
+        cmd: Option<BiblesCommand>,
+    },
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+pub enum BiblesCommand {
+    /// List the Bibles kyro can get and which are installed, the default
+    List,
+    /// Download a Bible
+    Install { id: String },
+    /// Delete a Bible from this computer
+    Remove { id: String },
+    /// Download installed Bibles again if they have changed, all of them without an id
+    Update { id: Option<String> },
+}
+
+impl BiblesCommand {
+    pub fn run(&self, config: &Config, registry: &Registry) -> anyhow::Result<()> {
+        let data_dir = get_data_dir().context("couldn't determine data dir path")?;
+        let fetcher = Fetchers::new(config)?;
+        match self {
+            BiblesCommand::List => {
+                for line in bibles::list(&data_dir, registry) {
+                    println!("{}", line);
+                }
+            }
+            BiblesCommand::Install { id } => {
+                let translation = bibles::install(&data_dir, registry, &fetcher, id)?;
+                println!("installed {}", translation.name);
+            }
+            BiblesCommand::Remove { id } => {
+                bibles::remove(&data_dir, registry, &user_manifest_path()?, id)?;
+                println!("removed {}", id);
+            }
+            BiblesCommand::Update { id } => {
+                let updated = bibles::update(&data_dir, registry, &fetcher, id.as_deref())?;
+                if updated.is_empty() {
+                    println!("everything is up to date");
+                }
+                for id in updated {
+                    println!("updated {}", id);
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Command {
-    pub fn run(&self, config: &Config) -> anyhow::Result<()> {
+    pub fn run(
+        &self,
+        config: &Config,
+        registry: &Registry,
+        bible_name: Option<&str>,
+        lang: Option<&str>,
+    ) -> anyhow::Result<()> {
+        //importing and managing Bibles don't need one to already be there
+        let bible = || show::setup_bible(config, registry, bible_name, lang);
         match self {
             Command::Search {
-                book: book_title,
-                chapter_verse,
+                reference,
+                note_opts,
+                copyright,
             } => {
-                let mut book = show::setup_a_book(book_title.to_string(), config)?;
-                let mut query = Query::setup_query(chapter_verse.to_string())?;
-                show::print_passage(&mut book, &mut query)
+                let bible = bible()?;
+                let references = Reference::parse_list(&reference.join(" "))?;
+                //check every one of them before printing any
+                let found = references
+                    .iter()
+                    .map(|r| r.passages(&bible))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let mut renderer =
+                    Renderer::new(note_opts.show_notes(config), config.styled_output());
+                for (i, (reference, passages)) in references.iter().zip(&found).enumerate() {
+                    //a header for each passage of a list so they can be told apart
+                    if references.len() > 1 {
+                        if i > 0 {
+                            println!();
+                        }
+                        println!("{}", reference);
+                    }
+                    for (book, query) in passages {
+                        //a passage running over into other books says where each one starts
+                        if passages.len() > 1 {
+                            println!("{}", book.title);
+                        }
+                        show::print_passage(book, query, &mut renderer)?;
+                    }
+                }
+                if *copyright || config.copyright {
+                    show::print_copyright(&bible);
+                }
+                Ok(())
             }
             Command::Read {
-                book: book_title,
-                chapter: chapter_verse,
+                reference,
+                note_opts,
+            } => {
+                let bible = bible()?;
+                let reference = Reference::parse(&reference.join(" "))?;
+                let (book, query) = reference.passages(&bible)?[0];
+                let mut renderer =
+                    Renderer::new(note_opts.show_notes(config), config.styled_output());
+                //without a chapter the whole book is read from the beginning
+                let start = reference.start.chapter.map(|_| &query);
+                show::read_passage(book, start, &mut renderer)
+            }
+            Command::Today => show::today(&bible()?, config, gen_seed_from_date()),
+            Command::Info => {
+                show::info(&bible()?);
+                Ok(())
+            }
+            Command::Import {
+                path,
+                name,
+                language,
             } => {
-                let book = show::setup_a_book(book_title.to_string(), config)?;
-                if let Some(passage) = chapter_verse {
-                    let query = Query::setup_query(passage.to_string())?;
-                    show::read_passage(&book, Some(&query))
-                } else {
-                    show::read_passage(&book, None)
+                let opts = ImportOpts {
+                    name: name.clone(),
+                    language: language.clone(),
+                };
+                import_bible(path, &opts, registry)
+            }
+            Command::Bibles { catalog, cmd } => {
+                let cmd = cmd.as_ref().unwrap_or(&BiblesCommand::List);
+                match catalog {
+                    Some(catalog) => cmd.run(config, &Registry::with_catalog(catalog)?),
+                    None => cmd.run(config, registry),
                 }
             }
-            Command::Today => show::today(config, gen_seed_from_date()),
         }
     }
 }
@@ -57,9 +250,127 @@ mod tests {
     #[ignore]
     fn test_run() {
         let cmd = Command::Search {
-            book: "John".to_string(),
-            chapter_verse: "3:16".to_string(),
+            reference: vec!["John".to_string(), "3:16".to_string()],
+            note_opts: NoteOpts::default(),
+            copyright: false,
         };
-        cmd.run(&Config::default()).unwrap();
+        cmd.run(&Config::default(), &Registry::load().unwrap(), None, None)
+            .unwrap();
+    }
+
+    #[test]
+    fn notes_flags() {
+        let mut config = Config::default();
+        let opts = Opts::from_iter(&["kyro", "search", "John", "3:16", "--notes"]);
+        if let Command::Search { note_opts, .. } = opts.cmd {
+            assert!(note_opts.show_notes(&config));
+        } else {
+            panic!("expected a search command");
+        }
+
+        config.notes = true;
+        let opts = Opts::from_iter(&["kyro", "read", "John", "--notes", "--no-notes"]);
+        if let Command::Read { note_opts, .. } = opts.cmd {
+            assert!(!note_opts.show_notes(&config));
+        } else {
+            panic!("expected a read command");
+        }
+    }
+
+    #[test]
+    fn one_reference() {
+        let opts = Opts::from_iter(&["kyro", "search", "1 Cor 13:4-7"]);
+        if let Command::Search { reference, .. } = opts.cmd {
+            assert_eq!(reference, vec!["1 Cor 13:4-7"]);
+        } else {
+            panic!("expected a search command");
+        }
+
+        //without quotes the words of the reference come in one by one
+        let opts = Opts::from_iter(&["kyro", "read", "song", "of", "songs", "2"]);
+        if let Command::Read { reference, .. } = opts.cmd {
+            let reference = Reference::parse(&reference.join(" ")).unwrap();
+            assert_eq!(reference.start.book, "Song of Solomon");
+        } else {
+            panic!("expected a read command");
+        }
+    }
+
+    #[test]
+    fn copyright_flag() {
+        let opts = Opts::from_iter(&["kyro", "search", "John", "3:16", "--copyright"]);
+        assert!(matches!(
+            opts.cmd,
+            Command::Search {
+                copyright: true,
+                ..
+            }
+        ));
+        let opts = Opts::from_iter(&["kyro", "info", "--bible", "myversion"]);
+        assert!(matches!(opts.cmd, Command::Info));
+    }
+
+    #[test]
+    fn bible_flag() {
+        let opts = Opts::from_iter(&["kyro", "today", "--bible", "myversion"]);
+        assert_eq!(opts.bible.as_deref(), Some("myversion"));
+
+        let opts = Opts::from_iter(&[
+            "kyro",
+            "--lang=french",
+            "--bible=ost",
+            "search",
+            "Jean",
+            "3:16",
+        ]);
+        assert_eq!(opts.bible.as_deref(), Some("ost"));
+        assert_eq!(opts.lang.as_deref(), Some("french"));
+        let opts = Opts::from_iter(&["kyro", "read", "John", "-l", "eng", "-b", "net"]);
+        assert_eq!(opts.bible.as_deref(), Some("net"));
+        assert_eq!(opts.lang.as_deref(), Some("eng"));
+
+        let opts = Opts::from_iter(&["kyro", "import", "draft.zip", "--name", "draft"]);
+        assert!(opts.bible.is_none());
+        if let Command::Import { path, name, .. } = opts.cmd {
+            assert_eq!(path, PathBuf::from("draft.zip"));
+            assert_eq!(name.as_deref(), Some("draft"));
+        } else {
+            panic!("expected an import command");
+        }
+    }
+
+    #[test]
+    fn bibles_commands() {
+        let opts = Opts::from_iter(&["kyro", "bibles"]);
+        assert!(matches!(opts.cmd, Command::Bibles { cmd: None, .. }));
+
+        let opts = Opts::from_iter(&[
+            "kyro",
+            "bibles",
+            "--catalog",
+            "catalog.toml",
+            "install",
+            "net",
+        ]);
+        if let Command::Bibles { catalog, cmd } = opts.cmd {
+            assert_eq!(catalog, Some(PathBuf::from("catalog.toml")));
+            assert_eq!(
+                cmd,
+                Some(BiblesCommand::Install {
+                    id: "net".to_string()
+                })
+            );
+        } else {
+            panic!("expected a bibles command");
+        }
+
+        let opts = Opts::from_iter(&["kyro", "bibles", "update"]);
+        assert!(matches!(
+            opts.cmd,
+            Command::Bibles {
+                cmd: Some(BiblesCommand::Update { id: None }),
+                ..
+            }
+        ));
     }
 }
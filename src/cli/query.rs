@@ -1,7 +1,7 @@
 use crate::bible::passage::Point;
-use anyhow::Context;
 
-#[derive(Debug, Clone, Copy)]
+///A passage of one book from a verse up to and including another
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Query {
     pub entry_point: Point,
     pub end_point: Point,
@@ -46,83 +46,6 @@ impl Query {
             self.vs_next += 1;
         }
     }
-
-    pub fn setup_query(chapter_verse: String) -> anyhow::Result<Query> {
-        let (start_point, end_point) = Query::split_cli_query(&chapter_verse)?;
-        let query = Query::new(start_point, end_point);
-        Ok(query)
-    }
-
-    pub fn get_query_start(start_vec: &[&str]) -> anyhow::Result<Point> {
-        let start_chpt = start_vec[0]
-            .parse::<u32>()
-            .context("starting chapter invalid")?;
-
-        let start_vs: u32 = if start_vec.len() > 1 {
-            start_vec[1]
-                .parse::<u32>()
-                .context("starting verse is invalid")?
-        } else {
-            1
-        };
-
-        let start = Point {
-            chpt: start_chpt,
-            verse: start_vs,
-        };
-
-        Ok(start)
-    }
-
-    pub fn split_cli_query(chpt_vs_query: &str) -> anyhow::Result<(Point, Point)> {
-        let dash = '-';
-        let colon = ':';
-
-        // if the user just enters the book title and no chapter:verse then start them off at the
-        // beginning of the book
-        if chpt_vs_query.is_empty() {
-            return Ok((Point::new(1, 1), Point::new(1, 0)));
-        }
-
-        let split_on_dash: Vec<&str> = chpt_vs_query.split(dash).collect();
-
-        let start_vec: Vec<&str> = split_on_dash[0].split(colon).collect();
-        let start = Query::get_query_start(&start_vec)?;
-
-        let no_end_point_provided = split_on_dash.len() == 1;
-        let end: Point = if no_end_point_provided {
-            Point::new(0, 0)
-        } else {
-            //WARNING:this only exists if there is a dash
-            let end_vec: Vec<&str> = split_on_dash[1].split(colon).collect();
-
-            let has_chpt = end_vec.len() == 2;
-            let end_chpt = if has_chpt {
-                end_vec[0]
-                    .parse::<u32>()
-                    .context("ending chapter is invalid")?
-            } else {
-                start.chpt
-            };
-
-            let end_vs = if has_chpt {
-                end_vec[1]
-                    .parse::<u32>()
-                    .context("ending verse is invalid")?
-            } else {
-                end_vec[0]
-                    .parse::<u32>()
-                    .context("ending verse is invalid")?
-            };
-
-            Point {
-                chpt: end_chpt,
-                verse: end_vs,
-            }
-        };
-
-        Ok((start, end))
-    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,920 @@
+mod error;
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+pub use self::error::{QueryError, QueryErrorKind};
+use crate::bible::book::{canonical_position, title_from_id, Book};
+use crate::bible::passage::Point;
+use crate::{Bible, Query};
+use anyhow::Context;
+
+///how many books to offer when a name isn't one we know
+const MAX_SUGGESTIONS: usize = 3;
+
+///Other names for each book by its Paratext id: the OSIS id, the SBL abbreviations and the names
+///and abbreviations people commonly use. The title and the Paratext id always match as well.
+const BOOK_NAMES: &[(&str, &[&str])] = &[
+    ("GEN", &["Gen", "Gn", "Ge"]),
+    ("EXO", &["Exod", "Ex", "Exo"]),
+    ("LEV", &["Lev", "Lv", "Le"]),
+    ("NUM", &["Num", "Nm", "Nb", "Nu"]),
+    ("DEU", &["Deut", "Dt", "De"]),
+    ("JOS", &["Josh", "Jos", "Jsh"]),
+    ("JDG", &["Judg", "Jdg", "Jg", "Jdgs"]),
+    ("RUT", &["Ruth", "Rth", "Ru"]),
+    ("1SA", &["1 Sam", "1 Sm", "1 Sa", "1 Samuel"]),
+    ("2SA", &["2 Sam", "2 Sm", "2 Sa", "2 Samuel"]),
+    ("1KI", &["1 Kgs", "1 Kg", "1 Ki", "1 Kin", "1 Kings"]),
+    ("2KI", &["2 Kgs", "2 Kg", "2 Ki", "2 Kin", "2 Kings"]),
+    ("1CH", &["1 Chr", "1 Ch", "1 Chron", "1 Chronicles"]),
+    ("2CH", &["2 Chr", "2 Ch", "2 Chron", "2 Chronicles"]),
+    ("EZR", &["Ezra", "Ezr"]),
+    ("NEH", &["Neh", "Ne"]),
+    ("EST", &["Esth", "Est", "Es"]),
+    ("JOB", &["Job", "Jb"]),
+    ("PSA", &["Ps", "Pss", "Psa", "Psalm", "Pslm", "Psm"]),
+    ("PRO", &["Prov", "Pr", "Prv", "Pro"]),
+    ("ECC", &["Eccl", "Ecc", "Ec", "Qoh", "Qoheleth"]),
+    (
+        "SNG",
+        &["Song", "Song of Songs", "Sg", "Cant", "Canticles", "SoS"],
+    ),
+    ("ISA", &["Isa", "Is"]),
+    ("JER", &["Jer", "Je", "Jr"]),
+    ("LAM", &["Lam", "La"]),
+    ("EZK", &["Ezek", "Eze", "Ezk"]),
+    ("DAN", &["Dan", "Da", "Dn"]),
+    ("HOS", &["Hos", "Ho"]),
+    ("JOL", &["Joel", "Jl"]),
+    ("AMO", &["Amos", "Am"]),
+    ("OBA", &["Obad", "Ob"]),
+    ("JON", &["Jonah", "Jnh"]),
+    ("MIC", &["Mic", "Mc"]),
+    ("NAM", &["Nah", "Na"]),
+    ("HAB", &["Hab", "Hb"]),
+    ("ZEP", &["Zeph", "Zep", "Zp"]),
+    ("HAG", &["Hag", "Hg"]),
+    ("ZEC", &["Zech", "Zec", "Zc"]),
+    ("MAL", &["Mal", "Ml"]),
+    ("MAT", &["Matt", "Mt", "Mat"]),
+    ("MRK", &["Mark", "Mk", "Mrk", "Mar"]),
+    ("LUK", &["Luke", "Lk", "Luk"]),
+    ("JHN", &["John", "Jn", "Jhn"]),
+    ("ACT", &["Acts", "Ac"]),
+    ("ROM", &["Rom", "Ro", "Rm"]),
+    ("1CO", &["1 Cor", "1 Co", "1 Corinthians"]),
+    ("2CO", &["2 Cor", "2 Co", "2 Corinthians"]),
+    ("GAL", &["Gal", "Ga"]),
+    ("EPH", &["Eph", "Ephes"]),
+    ("PHP", &["Phil", "Php", "Pp"]),
+    ("COL", &["Col"]),
+    ("1TH", &["1 Thess", "1 Th", "1 Thes", "1 Thessalonians"]),
+    ("2TH", &["2 Thess", "2 Th", "2 Thes", "2 Thessalonians"]),
+    ("1TI", &["1 Tim", "1 Ti", "1 Timothy"]),
+    ("2TI", &["2 Tim", "2 Ti", "2 Timothy"]),
+    ("TIT", &["Titus", "Tit"]),
+    ("PHM", &["Phlm", "Phm", "Philem"]),
+    ("HEB", &["Heb"]),
+    ("JAS", &["Jas", "Jm", "Jam"]),
+    ("1PE", &["1 Pet", "1 Pe", "1 Pt", "1 Peter"]),
+    ("2PE", &["2 Pet", "2 Pe", "2 Pt", "2 Peter"]),
+    ("1JN", &["1 John", "1 Jn", "1 Jhn"]),
+    ("2JN", &["2 John", "2 Jn", "2 Jhn"]),
+    ("3JN", &["3 John", "3 Jn", "3 Jhn"]),
+    ("JUD", &["Jude", "Jud", "Jd"]),
+    ("REV", &["Rev", "Re", "Revelations", "Apocalypse", "Apoc"]),
+];
+
+///the dashes a range can be written with, a hyphen or an en or em dash (ie. Gen 50–Exod 2)
+const DASHES: &[char] = &['-', '\u{2013}', '\u{2014}'];
+
+///A place in the Bible as it was written, the parts that were left out are filled in when it's
+///looked up (ie. without a verse it's the start or the end of the chapter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bound {
+    ///the title of the book (ie. 1 Corinthians)
+    pub book: &'static str,
+    pub chapter: Option<u32>,
+    pub verse: Option<u32>,
+}
+
+///Where a reference stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    ///at the last verse of this, so the end of the chapter when there's no verse and the end of
+    ///the book when there's no chapter either
+    At(Bound),
+    ///at the end of the paragraph the reference starts in (ie. Ps 119:1ff)
+    Paragraph,
+}
+
+///Where the parts of a bound were written in the input, to point at them when they're wrong
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Marks {
+    all: Range<usize>,
+    chapter: Option<Range<usize>>,
+    verse: Option<Range<usize>>,
+}
+
+///A piece of the input and where it starts in it, so that mistakes in it can be pointed at
+#[derive(Debug, Clone, Copy)]
+struct Piece<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl<'a> Piece<'a> {
+    ///all of the input
+    fn new(text: &'a str) -> Self {
+        Self { text, at: 0 }
+    }
+
+    ///the bytes of the input it covers
+    fn range(&self) -> Range<usize> {
+        self.at..self.at + self.text.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn trim(self) -> Self {
+        let start = self.text.len() - self.text.trim_start().len();
+        Self {
+            text: self.text.trim(),
+            at: self.at + start,
+        }
+    }
+
+    fn strip_suffix(self, suffix: &str) -> Option<Self> {
+        let text = self.text.strip_suffix(suffix)?;
+        Some(Self { text, at: self.at })
+    }
+
+    ///the piece before a byte and the piece from it on
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        let (before, after) = self.text.split_at(mid);
+        (
+            Self {
+                text: before,
+                at: self.at,
+            },
+            Self {
+                text: after,
+                at: self.at + mid,
+            },
+        )
+    }
+
+    ///the pieces either side of the first of any of these characters
+    fn split_once(self, chars: &[char]) -> Option<(Self, Self)> {
+        let i = self.text.find(chars)?;
+        let (before, rest) = self.split_at(i);
+        let width = rest.text.chars().next()?.len_utf8();
+        Some((before, rest.split_at(width).1))
+    }
+
+    ///the pieces between each `c`
+    fn split(self, c: char) -> impl Iterator<Item = Piece<'a>> {
+        let mut at = self.at;
+        self.text.split(c).map(move |text| {
+            let piece = Piece { text, at };
+            at += text.len() + c.len_utf8();
+            piece
+        })
+    }
+}
+
+///A passage picked out by a reference like `1 Cor 13:4-7`, which can run on into other books
+///(ie. Gen 50-Exod 2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub start: Bound,
+    pub end: End,
+    ///the text it was parsed from, which has the others in it too when it came from a list
+    input: String,
+    ///where the start and the end were written in the input
+    written: (Marks, Marks),
+}
+
+impl Bound {
+    ///the whole of a book
+    fn book(book: &'static str) -> Self {
+        Self {
+            book,
+            chapter: None,
+            verse: None,
+        }
+    }
+
+    ///Where it is in the Bible so that bounds can be put in order. The parts that were left out
+    ///count as `missing` (ie. 0 for the start of something and u32::MAX for the end).
+    fn order(&self, missing: u32) -> (Option<u32>, Point) {
+        (
+            canonical_position(self.book),
+            Point::new(
+                self.chapter.unwrap_or(missing),
+                self.verse.unwrap_or(missing),
+            ),
+        )
+    }
+}
+
+impl Reference {
+    ///Parse a reference, the book can be any of the names or abbreviations we know for it in
+    ///any case (ie. "1 Cor 13:4-7", "1co 13:4-7" or "first corinthians 13"). A range can end in
+    ///another book (ie. "Gen 50-Exod 2"), run on to the end of the chapter or book when there's
+    ///nothing after the dash (ie. "John 3:16-") or to the end of the paragraph (ie. "Ps 119:1ff").
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        Reference::parse_in(input, Piece::new(input), None, None)
+    }
+
+    ///Parse a list of references separated by `,` or `;` (ie. "John 3:16,18; Rom 8:28-39").
+    ///Each one carries on in the book before it when it doesn't name one, and after a comma
+    ///following a verse a bare number is another verse of the same chapter.
+    pub fn parse_list(input: &str) -> anyhow::Result<Vec<Self>> {
+        let mut references = Vec::new();
+        let mut book = None;
+        for group in Piece::new(input).split(';') {
+            let mut chapter = None;
+            for part in group.split(',').filter(|p| !p.trim().is_empty()) {
+                let reference = Reference::parse_in(input, part, book, chapter)?;
+                //only a verse can be followed by more verses of its chapter
+                let (last_book, last_chapter) = match reference.end {
+                    End::At(end) => (end.book, end.verse.and(end.chapter)),
+                    End::Paragraph => (reference.start.book, reference.start.chapter),
+                };
+                book = Some(last_book);
+                chapter = last_chapter;
+                references.push(reference);
+            }
+        }
+        anyhow::ensure!(!references.is_empty(), "there's no reference to look up");
+        Ok(references)
+    }
+
+    ///parse one reference out of part of the input that's in `book` when it doesn't name one,
+    ///where a bare number is a verse of `chapter` when there is one
+    fn parse_in(
+        input: &str,
+        part: Piece,
+        book: Option<&'static str>,
+        chapter: Option<u32>,
+    ) -> anyhow::Result<Self> {
+        let part = part.trim();
+        let (text, following) = match part.strip_suffix("ff") {
+            Some(text) if text.text.ends_with(|c: char| c.is_ascii_digit()) => (text, true),
+            _ => (part, false),
+        };
+        let (start_text, end_text) = match text.split_once(DASHES) {
+            Some((start, end)) => (start.trim(), Some(end.trim())),
+            None => (text, None),
+        };
+        anyhow::ensure!(
+            !(following && end_text.is_some()),
+            "{} can't end twice, use either a range or ff",
+            part.text
+        );
+
+        let (name, chapter_verse) = split_reference(start_text);
+        let (book, chapter) = if name.is_empty() {
+            (book, chapter)
+        } else {
+            (Some(lookup(input, name)?), None)
+        };
+        let book = book.with_context(|| format!("{} doesn't say which book", part.text))?;
+        let (start, start_marks) = bound(start_text, book, chapter_verse, chapter)?;
+
+        let (end, end_marks) = match end_text {
+            None if following && start.verse.is_some() => (End::Paragraph, start_marks.clone()),
+            None if !following => (End::At(start), start_marks.clone()),
+            Some(end_text) if !end_text.is_empty() => {
+                let (name, chapter_verse) = split_reference(end_text);
+                let (end, marks) = if name.is_empty() {
+                    let chapter = start.verse.and(start.chapter);
+                    bound(end_text, book, chapter_verse, chapter)?
+                } else {
+                    let book = lookup(input, name)?;
+                    bound(end_text, book, chapter_verse, None)?
+                };
+                (End::At(end), marks)
+            }
+            //the rest of the chapter when there's a verse to start at and of the book otherwise
+            _ => {
+                let end = match start {
+                    Bound { verse: Some(_), .. } => Bound {
+                        verse: None,
+                        ..start
+                    },
+                    Bound {
+                        chapter: Some(_), ..
+                    } => Bound::book(book),
+                    _ => anyhow::bail!("{} doesn't say where to start", part.text),
+                };
+                (End::At(end), start_marks.clone())
+            }
+        };
+
+        if let End::At(end) = end {
+            if start.order(0) > end.order(u32::MAX) {
+                let at = end_marks.all.clone();
+                return Err(QueryError::new(QueryErrorKind::ReversedRange, input, at).into());
+            }
+        }
+        Ok(Self {
+            start,
+            end,
+            input: input.to_string(),
+            written: (start_marks, end_marks),
+        })
+    }
+
+    ///The part of each book the reference covers, with whatever it leaves out filled in from
+    ///the Bible (ie. John 3 is John 3:1-36). Every chapter and verse has to be in the Bible.
+    pub fn passages<'b>(&self, bible: &'b Bible) -> anyhow::Result<Vec<(&'b Book, Query)>> {
+        let (start_marks, end_marks) = &self.written;
+        let first = bible.book(self.start.book)?;
+        let start = self.point(first, &self.start, start_marks, false)?;
+        let (last, end) = match self.end {
+            End::At(end) => {
+                let book = bible.book(end.book)?;
+                (book, self.point(book, &end, end_marks, true)?)
+            }
+            End::Paragraph => {
+                let verse = first
+                    .chapter(start.chpt)
+                    .and_then(|ch| ch.paragraph_end(start.verse))
+                    .with_context(|| format!("cannot find {}", self))?;
+                (first, Point::new(start.chpt, verse))
+            }
+        };
+        if first.title == last.title {
+            return Ok(vec![(first, Query::new(start, end))]);
+        }
+
+        //the books in between are read whole
+        let whole = |book: &Book| -> anyhow::Result<Query> {
+            let everything = Bound::book(self.start.book);
+            Ok(Query::new(
+                self.point(book, &everything, &Marks::default(), false)?,
+                self.point(book, &everything, &Marks::default(), true)?,
+            ))
+        };
+        let from = canonical_position(&first.title);
+        let to = canonical_position(&last.title);
+        let mut passages = vec![(first, Query::new(start, whole(first)?.end_point))];
+        for book in bible.books.iter().filter(|b| {
+            let position = canonical_position(&b.title);
+            position > from && position < to
+        }) {
+            passages.push((book, whole(book)?));
+        }
+        passages.push((last, Query::new(whole(last)?.entry_point, end)));
+        Ok(passages)
+    }
+
+    ///Where a bound is in a book, checking its chapter and verse are in there. What it leaves
+    ///out is the first verse of the chapter or book, or the `last` one.
+    fn point(
+        &self,
+        book: &Book,
+        bound: &Bound,
+        marks: &Marks,
+        last: bool,
+    ) -> anyhow::Result<Point> {
+        let ch = match bound.chapter {
+            Some(number) => book.chapter(number),
+            None if last => book.chapters.last(),
+            None => book.chapters.first(),
+        };
+        let ch = match (ch, bound.chapter) {
+            (Some(ch), _) => ch,
+            (None, Some(chapter)) => {
+                let kind = QueryErrorKind::ChapterOutOfRange {
+                    book: book.title.clone(),
+                    chapter,
+                    max: book.chapters.last().map_or(0, |c| c.number),
+                };
+                let at = marks.chapter.clone().unwrap_or_else(|| marks.all.clone());
+                return Err(QueryError::new(kind, &self.input, at).into());
+            }
+            (None, None) => anyhow::bail!("{} has no chapters", book.title),
+        };
+
+        let max = ch
+            .last_verse()
+            .with_context(|| format!("{} {} has no verses", book.title, ch.number))?;
+        let verse = match bound.verse {
+            Some(verse) if verse == 0 || verse > max => {
+                let kind = QueryErrorKind::VerseOutOfRange {
+                    book: book.title.clone(),
+                    chapter: ch.number,
+                    verse,
+                    max,
+                };
+                let at = marks.verse.clone().unwrap_or_else(|| marks.all.clone());
+                return Err(QueryError::new(kind, &self.input, at).into());
+            }
+            Some(verse) => verse,
+            None if last => max,
+            None => ch.first_verse().unwrap_or(max),
+        };
+        Ok(Point::new(ch.number, verse))
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.book)?;
+        if let Some(chapter) = self.chapter {
+            write!(f, " {}", chapter)?;
+        }
+        if let Some(verse) = self.verse {
+            write!(f, ":{}", verse)?;
+        }
+        Ok(())
+    }
+}
+
+///The reference written out in full the way it was asked for (ie. John 3:16-18 or John 3:16-)
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.start)?;
+        let end = match self.end {
+            End::Paragraph => return write!(f, "ff"),
+            End::At(end) if end == self.start => return Ok(()),
+            End::At(end) => end,
+        };
+        write!(f, "-")?;
+        if end.book != self.start.book {
+            return write!(f, "{}", end);
+        }
+
+        let same_chapter = end.chapter == self.start.chapter;
+        match (end.chapter, end.verse) {
+            //it runs on to the end of the book or chapter
+            (None, _) => Ok(()),
+            (Some(_), None) if same_chapter => Ok(()),
+            (Some(_), Some(verse)) if same_chapter && self.start.verse.is_some() => {
+                write!(f, "{}", verse)
+            }
+            (Some(chapter), None) => write!(f, "{}", chapter),
+            (Some(chapter), Some(verse)) => write!(f, "{}:{}", chapter, verse),
+        }
+    }
+}
+
+impl FromStr for Reference {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Reference::parse(s)
+    }
+}
+
+///A chapter and verse (ie. 3:16) written in `text` in a book. A bare number is a verse of
+///`chapter` when there is one and a chapter when there isn't, and nothing at all is the whole
+///book.
+fn bound(
+    text: Piece,
+    book: &'static str,
+    chapter_verse: Piece,
+    chapter: Option<u32>,
+) -> anyhow::Result<(Bound, Marks)> {
+    let mut marks = Marks {
+        all: text.range(),
+        ..Marks::default()
+    };
+    if chapter_verse.is_empty() {
+        return Ok((Bound::book(book), marks));
+    }
+    let (chapter, verse) = match (chapter_verse.split_once(&[':']), chapter) {
+        (Some((ch, vs)), _) => {
+            let (ch, vs) = (ch.trim(), vs.trim());
+            marks.chapter = Some(ch.range());
+            marks.verse = Some(vs.range());
+            (number(ch.text, "chapter")?, Some(number(vs.text, "verse")?))
+        }
+        (None, Some(ch)) => {
+            marks.verse = Some(chapter_verse.range());
+            (ch, Some(number(chapter_verse.text, "verse")?))
+        }
+        (None, None) => {
+            marks.chapter = Some(chapter_verse.range());
+            (number(chapter_verse.text, "chapter")?, None)
+        }
+    };
+    let bound = Bound {
+        book,
+        chapter: Some(chapter),
+        verse,
+    };
+    Ok((bound, marks))
+}
+
+fn number(text: &str, what: &str) -> anyhow::Result<u32> {
+    let text = text.trim();
+    text.parse::<u32>()
+        .with_context(|| format!("{} is not a {} number", text, what))
+}
+
+///split a reference into the name of the book and the chapter and verse, the name can start
+///with a number of its own (ie. 1 Cor 13:4 -> 1 Cor, 13:4) and there's no name without a letter
+///in it (ie. 18 -> "", 18)
+fn split_reference(input: Piece) -> (Piece, Piece) {
+    let input = input.trim();
+    let split = match input.text.find(char::is_alphabetic) {
+        Some(name_start) => input.text[name_start..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(input.text.len(), |i| name_start + i),
+        None => 0,
+    };
+    let (name, chapter_verse) = input.split_at(split);
+    (name.trim(), chapter_verse.trim())
+}
+
+///lower case without any spaces or full stops, with a leading roman numeral or ordinal as a
+///number (ie. "I Cor." -> 1cor)
+fn normalize(name: &str) -> String {
+    let lower = name.to_lowercase().replace('.', " ");
+    let mut words: Vec<&str> = lower.split_whitespace().collect();
+    if words.len() > 1 {
+        let number = match words[0] {
+            "i" | "first" | "1st" => Some("1"),
+            "ii" | "second" | "2nd" => Some("2"),
+            "iii" | "third" | "3rd" => Some("3"),
+            _ => None,
+        };
+        if let Some(number) = number {
+            words[0] = number;
+        }
+    }
+    words.concat()
+}
+
+///the title of a book, or an error suggesting the books it might have been
+fn lookup(input: &str, name: Piece) -> Result<&'static str, QueryError> {
+    find_book(name.text).ok_or_else(|| {
+        let kind = QueryErrorKind::UnknownBook {
+            name: name.text.to_string(),
+            suggestions: suggest(name.text),
+        };
+        QueryError::new(kind, input, name.range())
+    })
+}
+
+///every name of every book as (normalized name, title)
+fn all_names() -> impl Iterator<Item = (String, &'static str)> {
+    BOOK_NAMES.iter().flat_map(|(id, names)| {
+        let title = title_from_id(id).unwrap_or(id);
+        [title, *id]
+            .iter()
+            .chain(names.iter())
+            .map(move |name| (normalize(name), title))
+            .collect::<Vec<_>>()
+    })
+}
+
+///The title of a book from any of its names or abbreviations, or the start of one when only a
+///single book starts that way (ie. "Phile" -> Philemon)
+pub fn find_book(name: &str) -> Option<&'static str> {
+    let wanted = normalize(name);
+    if wanted.is_empty() {
+        return None;
+    }
+    if let Some((_, title)) = all_names().find(|(n, _)| *n == wanted) {
+        return Some(title);
+    }
+
+    let mut starts_with: Vec<&str> = all_names()
+        .filter(|(n, _)| n.starts_with(&wanted))
+        .map(|(_, title)| title)
+        .collect();
+    starts_with.dedup();
+    match starts_with.as_slice() {
+        [title] => Some(title),
+        _ => None,
+    }
+}
+
+///how many characters have to be added, removed or changed to turn one string into another
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != *cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+///the books with a name that's close to this one, closest first
+pub fn suggest(name: &str) -> Vec<&'static str> {
+    let wanted = normalize(name);
+    //allow about one typo for every three letters
+    let max_distance = (wanted.chars().count() / 3).max(1);
+
+    let mut close: Vec<(usize, &str)> = all_names()
+        .map(|(n, title)| (edit_distance(&wanted, &n), title))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    close.sort_by_key(|(distance, _)| *distance);
+
+    let mut titles: Vec<&str> = Vec::new();
+    for (_, title) in close {
+        if !titles.contains(&title) {
+            titles.push(title);
+        }
+    }
+    titles.truncate(MAX_SUGGESTIONS);
+    titles
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::bible::format;
+
+    const SAMPLE: &str = include_str!("../../tests/fixtures/sample.osis.xml");
+
+    #[rstest]
+    #[case("John", "John")]
+    #[case("john", "John")]
+    #[case("Jn", "John")]
+    #[case("JHN", "John")]
+    #[case("1Cor", "1 Corinthians")]
+    #[case("1 Co", "1 Corinthians")]
+    #[case("I Corinthians", "1 Corinthians")]
+    #[case("first john", "1 John")]
+    #[case("Song of Songs", "Song of Solomon")]
+    #[case("song of solomon", "Song of Solomon")]
+    #[case("Ps", "Psalms")]
+    #[case("Psalm", "Psalms")]
+    #[case("Gal.", "Galatians")]
+    #[case("galatians", "Galatians")]
+    #[case("1 Thess", "1 Thessalonians")]
+    #[case("Phlm", "Philemon")]
+    #[case("phile", "Philemon")]
+    #[case("Rev", "Revelation")]
+    fn book_names(#[case] name: &str, #[case] title: &str) {
+        assert_eq!(find_book(name), Some(title));
+    }
+
+    #[rstest]
+    #[case("jo")]
+    #[case("Corinthians")]
+    #[case("")]
+    fn ambiguous_or_unknown(#[case] name: &str) {
+        assert_eq!(find_book(name), None);
+    }
+
+    fn at(book: &'static str, chapter: Option<u32>, verse: Option<u32>) -> Bound {
+        Bound {
+            book,
+            chapter,
+            verse,
+        }
+    }
+
+    #[test]
+    fn parse_references() {
+        let reference = Reference::parse("1 Cor 13:4-7").unwrap();
+        assert_eq!(reference.start, at("1 Corinthians", Some(13), Some(4)));
+        assert_eq!(
+            reference.end,
+            End::At(at("1 Corinthians", Some(13), Some(7)))
+        );
+
+        let reference: Reference = "john3:16".parse().unwrap();
+        assert_eq!(reference.start, at("John", Some(3), Some(16)));
+        assert_eq!(reference.end, End::At(reference.start));
+
+        let reference = Reference::parse("  1 John ").unwrap();
+        assert_eq!(reference.start, at("1 John", None, None));
+
+        assert!(Reference::parse("3:16").is_err());
+        assert!(Reference::parse("John three").is_err());
+    }
+
+    #[rstest]
+    #[case("John 3:16", "John 3:16")]
+    #[case("John 3:16-18", "John 3:16-18")]
+    #[case("John 3:16-4:2", "John 3:16-4:2")]
+    #[case("John 3", "John 3")]
+    #[case("John 3-4", "John 3-4")]
+    #[case("John 3 - 4:2", "John 3-4:2")]
+    #[case("Gen 50\u{2013}Exod 2", "Genesis 50-Exodus 2")]
+    #[case("Gen 50:22-Exod 1:7", "Genesis 50:22-Exodus 1:7")]
+    #[case("Gen-Exod", "Genesis-Exodus")]
+    #[case("John 3:16-", "John 3:16-")]
+    #[case("John 3-", "John 3-")]
+    #[case("Ps 119:1ff", "Psalms 119:1ff")]
+    #[case("Ps 119ff", "Psalms 119-")]
+    fn written_out(#[case] input: &str, #[case] shown: &str) {
+        let reference = Reference::parse(input).unwrap();
+        assert_eq!(reference.to_string(), shown);
+        //and it reads back the same
+        let again = Reference::parse(shown).unwrap();
+        assert_eq!((again.start, again.end), (reference.start, reference.end));
+    }
+
+    #[test]
+    fn open_ended() {
+        let reference = Reference::parse("John 3:16-").unwrap();
+        assert_eq!(reference.end, End::At(at("John", Some(3), None)));
+        let reference = Reference::parse("John 3-").unwrap();
+        assert_eq!(reference.end, End::At(at("John", None, None)));
+        let reference = Reference::parse("Ps 119:1ff").unwrap();
+        assert_eq!(reference.end, End::Paragraph);
+    }
+
+    #[rstest]
+    #[case("John 3:")]
+    #[case("John 3:18-16")]
+    #[case("John 4-3")]
+    #[case("Exod 2-Gen 50")]
+    #[case("John 3:16-18ff")]
+    #[case("Johnff")]
+    #[case("John-")]
+    fn bad_references(#[case] input: &str) {
+        assert!(Reference::parse(input).is_err());
+    }
+
+    #[test]
+    fn passages_of_a_bible() {
+        let bible = format::parse_bible(SAMPLE).unwrap();
+        let points = |input: &str| -> Vec<(String, Point, Point)> {
+            Reference::parse(input)
+                .unwrap()
+                .passages(&bible)
+                .unwrap()
+                .iter()
+                .map(|(book, q)| (book.title.clone(), q.entry_point, q.end_point))
+                .collect()
+        };
+        let john = |start: (u32, u32), end: (u32, u32)| {
+            vec![(
+                "John".to_string(),
+                Point::new(start.0, start.1),
+                Point::new(end.0, end.1),
+            )]
+        };
+
+        assert_eq!(points("John 3:17"), john((3, 17), (3, 17)));
+        //the sample starts part way through the chapter
+        assert_eq!(points("John 3"), john((3, 16), (3, 19)));
+        assert_eq!(points("John"), john((3, 16), (3, 19)));
+        assert_eq!(points("John 3:17-"), john((3, 17), (3, 19)));
+        assert_eq!(points("John 3:16ff"), john((3, 16), (3, 18)));
+
+        //the books missing from the sample in between are skipped over
+        assert_eq!(
+            points("Ps 23:2-John 3:16"),
+            vec![
+                ("Psalms".to_string(), Point::new(23, 2), Point::new(23, 2)),
+                ("John".to_string(), Point::new(3, 16), Point::new(3, 16)),
+            ]
+        );
+
+        let missing = Reference::parse("John 4").unwrap();
+        assert!(missing.passages(&bible).is_err());
+        let missing = Reference::parse("Rom 8").unwrap();
+        assert!(missing.passages(&bible).is_err());
+    }
+
+    #[test]
+    fn reference_lists() {
+        let shown = |input: &str| -> Vec<String> {
+            Reference::parse_list(input)
+                .unwrap()
+                .iter()
+                .map(|r| r.to_string())
+                .collect()
+        };
+        assert_eq!(
+            shown("John 3:16,18; Rom 8:28-39"),
+            vec!["John 3:16", "John 3:18", "Romans 8:28-39"]
+        );
+        assert_eq!(
+            shown("Ps 23, 121; 1 Cor 13"),
+            vec!["Psalms 23", "Psalms 121", "1 Corinthians 13"]
+        );
+        assert_eq!(
+            shown("John 3:16-18, 20; 4:1, 3"),
+            vec!["John 3:16-18", "John 3:20", "John 4:1", "John 4:3"]
+        );
+        assert_eq!(
+            shown("Gen 1:1, Exod 3:14, John 1:1;"),
+            vec!["Genesis 1:1", "Exodus 3:14", "John 1:1"]
+        );
+        assert_eq!(shown("Jude"), vec!["Jude"]);
+
+        assert!(Reference::parse_list("3:16, 18").is_err());
+        assert!(Reference::parse_list(" ; ").is_err());
+        assert!(Reference::parse_list("John 3:16, Jhon 3:17").is_err());
+    }
+
+    #[test]
+    fn did_you_mean() {
+        assert!(suggest("Jhon").contains(&"John"));
+        assert_eq!(suggest("1 Corinthains")[0], "1 Corinthians");
+        assert!(suggest("xyzzy").is_empty());
+
+        let err = Reference::parse("Genisis 1:1").unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Genisis is not a book of the Bible, did you mean Genesis?\n    Genisis 1:1\n    ^^^^^^^"
+        );
+        let err = Reference::parse("Xyzzy 1:1").unwrap_err().to_string();
+        assert!(err.starts_with("Xyzzy is not a book of the Bible\n"));
+    }
+
+    ///the kind of mistake and the part of the input it points at
+    fn mistake(err: anyhow::Error) -> (QueryErrorKind, String) {
+        let err = err.downcast::<QueryError>().unwrap();
+        let at = err.input[err.at.clone()].to_string();
+        (err.kind, at)
+    }
+
+    #[test]
+    fn mistakes_in_the_text() {
+        let parsed = |input: &str| mistake(Reference::parse_list(input).unwrap_err());
+
+        let (kind, at) = parsed("John 3:16; Jhon 3:17");
+        assert!(matches!(kind, QueryErrorKind::UnknownBook { .. }));
+        assert_eq!(at, "Jhon");
+        assert_eq!(
+            parsed("John 3:18-16"),
+            (QueryErrorKind::ReversedRange, "16".to_string())
+        );
+        assert_eq!(
+            parsed("Exod 2\u{2013}Gen 50"),
+            (QueryErrorKind::ReversedRange, "Gen 50".to_string())
+        );
+        assert_eq!(
+            parsed("John 3:16, 4:2-1"),
+            (QueryErrorKind::ReversedRange, "1".to_string())
+        );
+    }
+
+    #[rstest]
+    #[case("John 40", "40", QueryErrorKind::ChapterOutOfRange { book: "John".to_string(), chapter: 40, max: 3 })]
+    #[case("John 3:99", "99", QueryErrorKind::VerseOutOfRange { book: "John".to_string(), chapter: 3, verse: 99, max: 19 })]
+    #[case("John 3:0", "0", QueryErrorKind::VerseOutOfRange { book: "John".to_string(), chapter: 3, verse: 0, max: 19 })]
+    #[case("John 3:16-20", "20", QueryErrorKind::VerseOutOfRange { book: "John".to_string(), chapter: 3, verse: 20, max: 19 })]
+    #[case("Ps 23-24", "24", QueryErrorKind::ChapterOutOfRange { book: "Psalms".to_string(), chapter: 24, max: 23 })]
+    #[case("Ps 23:1-John 4", "4", QueryErrorKind::ChapterOutOfRange { book: "John".to_string(), chapter: 4, max: 3 })]
+    fn out_of_range(#[case] input: &str, #[case] at: &str, #[case] kind: QueryErrorKind) {
+        let bible = format::parse_bible(SAMPLE).unwrap();
+        let reference = Reference::parse(input).unwrap();
+        let err = reference.passages(&bible).err().unwrap();
+        assert_eq!(mistake(err), (kind, at.to_string()));
+    }
+
+    #[test]
+    fn verses_in_a_list() {
+        let bible = format::parse_bible(SAMPLE).unwrap();
+        let references = Reference::parse_list("John 3:16, 99").unwrap();
+        assert!(references[0].passages(&bible).is_ok());
+
+        let err = references[1].passages(&bible).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "John 3 only goes up to verse 19\n    John 3:16, 99\n               ^^"
+        );
+    }
+
+    #[test]
+    fn pieces_of_the_input() {
+        let input = " Gen 50\u{2013}Exod 2; Ps 23";
+        let text = |p: Piece<'static>| (p.text, p.range());
+        let pieces: Vec<Piece> = Piece::new(input).split(';').collect();
+        let (start, end) = pieces[0].trim().split_once(DASHES).unwrap();
+        assert_eq!(text(start), ("Gen 50", 1..7));
+        assert_eq!(text(end), ("Exod 2", 10..16));
+        assert_eq!(&input[end.range()], "Exod 2");
+
+        let (name, chapter_verse) = split_reference(pieces[1]);
+        assert_eq!(text(name), ("Ps", 18..20));
+        assert_eq!(text(chapter_verse), ("23", 21..23));
+        let (name, chapter_verse) = split_reference(Piece::new(" 18 "));
+        assert_eq!(text(name), ("", 1..1));
+        assert_eq!(text(chapter_verse), ("18", 1..3));
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(edit_distance("john", "john"), 0);
+        assert_eq!(edit_distance("jhon", "john"), 2);
+        assert_eq!(edit_distance("", "gen"), 3);
+        assert_eq!(edit_distance("genisis", "genesis"), 1);
+    }
+}
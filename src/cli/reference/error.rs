@@ -0,0 +1,125 @@
+use std::fmt;
+use std::ops::Range;
+
+///What's wrong with a reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryErrorKind {
+    ///a name that isn't any book we know, with the closest ones to it
+    UnknownBook {
+        name: String,
+        suggestions: Vec<&'static str>,
+    },
+    ///a chapter past the end of the book
+    ChapterOutOfRange {
+        book: String,
+        chapter: u32,
+        max: u32,
+    },
+    ///a verse past the end of the chapter
+    VerseOutOfRange {
+        book: String,
+        chapter: u32,
+        verse: u32,
+        max: u32,
+    },
+    ///a range that ends before it starts (ie. John 3:18-16)
+    ReversedRange,
+}
+
+///A mistake in a reference and where it is in the input, so that it can be pointed out:
+///
+///```text
+///John only goes up to chapter 21
+///    John 40
+///         ^^
+///```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub kind: QueryErrorKind,
+    pub input: String,
+    ///the bytes of the input that are wrong
+    pub at: Range<usize>,
+}
+
+impl QueryError {
+    pub fn new(kind: QueryErrorKind, input: &str, at: Range<usize>) -> Self {
+        Self {
+            kind,
+            input: input.to_string(),
+            at,
+        }
+    }
+}
+
+impl fmt::Display for QueryErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryErrorKind::UnknownBook { name, suggestions } if suggestions.is_empty() => {
+                write!(f, "{} is not a book of the Bible", name)
+            }
+            QueryErrorKind::UnknownBook { name, suggestions } => write!(
+                f,
+                "{} is not a book of the Bible, did you mean {}?",
+                name,
+                suggestions.join(" or ")
+            ),
+            QueryErrorKind::ChapterOutOfRange { book, max, .. } => {
+                write!(f, "{} only goes up to chapter {}", book, max)
+            }
+            QueryErrorKind::VerseOutOfRange {
+                book, chapter, max, ..
+            } => write!(f, "{} {} only goes up to verse {}", book, chapter, max),
+            QueryErrorKind::ReversedRange => write!(f, "the range ends before it starts"),
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        //line the carets up by characters rather than bytes (ie. under an en dash), and under the
+        //end of the input when `at` isn't in it
+        let count = |range: Range<usize>| self.input.get(range).map(|s| s.chars().count());
+        let before = count(0..self.at.start).unwrap_or_else(|| self.input.chars().count());
+        let width = count(self.at.clone()).unwrap_or_default().max(1);
+        writeln!(f, "{}", self.kind)?;
+        writeln!(f, "    {}", self.input)?;
+        write!(f, "    {}{}", " ".repeat(before), "^".repeat(width))
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carets_under_the_mistake() {
+        let err = QueryError::new(
+            QueryErrorKind::VerseOutOfRange {
+                book: "John".to_string(),
+                chapter: 3,
+                verse: 99,
+                max: 36,
+            },
+            "John 3:99",
+            7..9,
+        );
+        assert_eq!(
+            err.to_string(),
+            "John 3 only goes up to verse 36\n    John 3:99\n           ^^"
+        );
+
+        let err = QueryError::new(QueryErrorKind::ReversedRange, "Gen 50\u{2013}1", 9..10);
+        assert_eq!(
+            err.to_string(),
+            "the range ends before it starts\n    Gen 50\u{2013}1\n           ^"
+        );
+
+        let err = QueryError::new(QueryErrorKind::ReversedRange, "Gen", 7..12);
+        assert_eq!(
+            err.to_string(),
+            "the range ends before it starts\n    Gen\n       ^"
+        );
+    }
+}
@@ -1,6 +1,5 @@
 use std::io::{self, Write};
 
-use anyhow::Context;
 use chrono::{Datelike, Local};
 use pager::Pager;
 use rand::{Rng, SeedableRng};
@@ -8,119 +7,51 @@ use rand_chacha::ChaCha8Rng;
 
 use crate::{
     bible::{
-        book::{Book, BOOK_ORDER},
+        book::Book,
         chapter::Chapter,
         paragraph::Paragraph,
-        verse::Verse,
+        render::Renderer,
+        verse::{Verse, VerseId},
+        Bible,
     },
-    bible_as_str, get_path_to_bible_file, Config, Query,
+    cache, require_bible, Config, Query, Registry,
 };
 
-pub fn find_pgh_idx(ch: &Chapter, verse_num: u32) -> Option<usize> {
-    for i in 0..ch.paragraphs.len() {
-        let opt = ch.paragraphs[i]
-            .verses
-            .iter()
-            .find(|v| v.number == verse_num);
-        if opt.is_some() {
-            return Some(i);
-        }
+///Print exactly the verses of a query, then the notes on them when they're shown
+pub fn print_passage(book: &Book, query: &Query, renderer: &mut Renderer) -> anyhow::Result<()> {
+    let passage = book.passage(query);
+    if passage.is_empty() {
+        anyhow::bail!(
+            "cannot find {} {}:{}",
+            book.title,
+            query.entry_point.chpt,
+            query.entry_point.verse
+        );
     }
-    None
-}
+    println!("{}", renderer.passage(&passage).trim_end());
 
-//HACK: the nesting is so gross...
-pub fn print_first_chapter(ch: &Chapter, query: &Query) -> anyhow::Result<()> {
-    let start_vs = query.entry_point.verse;
-    let end_vs = query.end_point.verse;
-
-    //the paragraph that contains the starting verse
-    let first_pgh_idx =
-        find_pgh_idx(ch, start_vs).context(format!("cannot find verse {} ", start_vs))?;
-    // option for the last paragraph of the query - only Some if the query is within a single
-    // chapter
-    let last_pgh_idx_opt = find_pgh_idx(ch, end_vs);
-
-    if query.is_range() {
-        let last_idx: usize;
-        //query doesn't span outside of the chapter
-        if query.is_internal_range() {
-            if let Some(ending) = last_pgh_idx_opt {
-                last_idx = ending;
-
-                if last_idx == first_pgh_idx {
-                    println!("{}", ch.paragraphs[first_pgh_idx]);
-                } else {
-                    // be carefull both idx's aren't the same
-                    for i in first_pgh_idx..last_idx {
-                        println!("{}", ch.paragraphs[i]);
-                    }
-                }
-            }
-        } else {
-            //range spans multiple chapters
-            last_idx = ch.paragraphs.len();
-            for i in first_pgh_idx..last_idx {
-                println!("{}", ch.paragraphs[i]);
-            }
-        }
-    } else {
-        //single verse queried
-        let pgh: &Paragraph = &ch.paragraphs[first_pgh_idx];
-        let vs: &Verse = pgh
-            .verses
-            .iter()
-            .find(|v| v.number == start_vs)
-            .expect("cannot find the desired verse");
-        println!("{}", vs);
-    }
-    Ok(())
-}
-
-pub fn print_last_chapter(ch: &Chapter, query: &Query) {
-    //for the last chapter print from the beginning to the ending point
-    //filter the paragraphs so that we only get the ones upto and including the ending vs
-    let end_vs = query.end_point.verse;
-    let final_phgs_iter = ch.paragraphs.iter().filter(|p| {
-        let opt = p.verses.iter().find(|v| v.number <= end_vs);
-        opt.is_some()
-    });
-    for pgh in final_phgs_iter {
-        println!("{}", pgh);
-    }
-}
-
-pub fn print_passage(book: &mut Book, query: &mut Query) -> anyhow::Result<()> {
-    //get the start and end points
-    let start_chpt = query.entry_point.chpt;
-    let end_chpt = query.end_point.chpt;
-
-    let chapters_iter = book.chapters.iter().filter(|c| {
-        if end_chpt != 0 {
-            c.number >= start_chpt && c.number <= end_chpt
-        } else {
-            c.number == start_chpt
-        }
-    });
-
-    for ch in chapters_iter {
-        if ch.number == start_chpt {
-            print_first_chapter(ch, query)?;
-        } else if ch.number < end_chpt {
-            //for all chapters in-between just print them to the screen
-            println!("{}", ch);
-        } else {
-            print_last_chapter(ch, query);
-        }
+    if let Some(notes) = renderer.take_notes() {
+        print!("\n{}", notes);
     }
 
     Ok(())
 }
 
-fn paginate(book: &Book, less_cmd: &str) -> anyhow::Result<()> {
+fn paginate(book: &Book, less_cmd: &str, renderer: &mut Renderer) -> anyhow::Result<()> {
     Pager::with_pager(less_cmd).setup();
 
-    let out: Vec<String> = book.chapters.iter().map(ToString::to_string).collect();
+    //in a whole book the notes are easier to find at the end of each chapter
+    let out: Vec<String> = book
+        .chapters
+        .iter()
+        .map(|ch| {
+            let mut text = renderer.chapter(ch);
+            if let Some(notes) = renderer.take_notes() {
+                text.push_str(&notes);
+            }
+            text
+        })
+        .collect();
     let result = io::stdout().write_all(out.join("\n").as_bytes());
 
     //HACK: pipes can break when the user quits the pager before reading the entire Book so
@@ -131,15 +62,19 @@ fn paginate(book: &Book, less_cmd: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn read_passage(book: &Book, query_opt: Option<&Query>) -> anyhow::Result<()> {
+pub fn read_passage(
+    book: &Book,
+    query_opt: Option<&Query>,
+    renderer: &mut Renderer,
+) -> anyhow::Result<()> {
     match query_opt {
         Some(query) => {
-            let less_cmd = format!("less -p ^CHAPTER_{}$", query.entry_point.chpt);
-            Ok(paginate(book, &less_cmd)?)
+            let less_cmd = format!("less -R -p ^CHAPTER_{}$", query.entry_point.chpt);
+            Ok(paginate(book, &less_cmd, renderer)?)
         }
         None => {
-            let less_cmd = "less";
-            Ok(paginate(book, less_cmd)?)
+            let less_cmd = "less -R";
+            Ok(paginate(book, less_cmd, renderer)?)
         }
     }
 }
@@ -148,6 +83,12 @@ fn num_gen(rng: &mut ChaCha8Rng, end_of_range: usize) -> usize {
     rng.gen_range(0..end_of_range)
 }
 
+///one of the items at random, or an error saying there are no `what` to pick from
+fn pick<'a, T>(rng: &mut ChaCha8Rng, items: &[&'a T], what: &str) -> anyhow::Result<&'a T> {
+    anyhow::ensure!(!items.is_empty(), "there are no {} to pick from", what);
+    Ok(items[num_gen(rng, items.len())])
+}
+
 pub fn gen_seed_from_date() -> u64 {
     let now = Local::now();
     let naive = now.naive_local();
@@ -159,45 +100,74 @@ pub fn gen_seed_from_date() -> u64 {
 }
 
 fn generate_verse_of_day(
-    config: &Config,
+    bible: &Bible,
     rng: &mut ChaCha8Rng,
-) -> anyhow::Result<(String, u32, u32, Verse)> {
-    let num_books = 66_usize;
-    let book_num = num_gen(rng, num_books) as u32;
-
-    let book_title: String = BOOK_ORDER
-        .get(&book_num)
-        .expect("book selected doesn't exist")
-        .to_string();
-
-    let book = setup_a_book(book_title, config)?;
-
-    let chpt_num = num_gen(rng, book.chapters.len());
-
-    let chpt: &Chapter = &book.chapters[chpt_num];
-
-    let pgh_num = num_gen(rng, chpt.paragraphs.len());
-    let pgh: &Paragraph = &chpt.paragraphs[pgh_num];
-
-    let vs_num = num_gen(rng, pgh.verses.len());
-    let vs: &Verse = &pgh.verses[vs_num];
-
-    Ok((book.title, chpt.number, vs.number, vs.to_owned()))
+) -> anyhow::Result<(String, u32, VerseId, Verse)> {
+    let books: Vec<&Book> = bible.books.iter().collect();
+    let book: &Book = pick(rng, &books, "books")?;
+
+    let chpts: Vec<&Chapter> = book.chapters.iter().collect();
+    let chpt: &Chapter = pick(rng, &chpts, &format!("chapters in {}", book.title))?;
+
+    //stanza breaks and the rest of a verse from the paragraph before have no verse to pick
+    let whole_verses = |p: &Paragraph| p.verses.iter().any(|v| !v.number.is_partial());
+    let pghs: Vec<&Paragraph> = chpt.paragraphs.iter().filter(|p| whole_verses(p)).collect();
+    let pgh: &Paragraph = pick(
+        rng,
+        &pghs,
+        &format!("verses in {} {}", book.title, chpt.number),
+    )?;
+
+    let verses: Vec<&Verse> = pgh
+        .verses
+        .iter()
+        .filter(|v| !v.number.is_partial())
+        .collect();
+    let vs: &Verse = pick(rng, &verses, "verses")?;
+
+    Ok((
+        book.title.to_string(),
+        chpt.number,
+        vs.number,
+        vs.to_owned(),
+    ))
 }
 
-pub fn today(config: &Config, seed: u64) -> anyhow::Result<()> {
+pub fn today(bible: &Bible, config: &Config, seed: u64) -> anyhow::Result<()> {
     let mut rng = ChaCha8Rng::seed_from_u64(seed);
-    let (title, chpter_num, vs_num, vs) = generate_verse_of_day(config, &mut rng)?;
-    println!("{} {}:{}\n{}", title, chpter_num, vs_num, vs);
+    let (title, chpter_num, vs_num, vs) = generate_verse_of_day(bible, &mut rng)?;
+    let mut renderer = Renderer::new(false, config.styled_output());
+    println!(
+        "{} {}:{}\n{}",
+        title,
+        chpter_num,
+        vs_num,
+        renderer.verse(&vs)
+    );
     Ok(())
 }
 
-pub fn setup_a_book(book_title: String, config: &Config) -> anyhow::Result<Book> {
-    let bible_str = bible_as_str(get_path_to_bible_file(config)?)?;
-    let bible_doc = roxmltree::Document::parse(&bible_str)?;
-    let title = book_title;
-    let book: Book = Book::new(title, &bible_doc)?;
-    Ok(book)
+///the short copyright line the translation asks for when it's quoted
+pub fn print_copyright(bible: &Bible) {
+    if let Some(copyright) = bible.metadata.short_copyright() {
+        println!("\n{}", copyright);
+    }
+}
+
+pub fn info(bible: &Bible) {
+    print!("{}", bible.metadata);
+    println!("Books: {}", bible.books.len());
+}
+
+///Load the Bible picked on the command line, or the one in the config when none was
+pub fn setup_bible(
+    config: &Config,
+    registry: &Registry,
+    bible: Option<&str>,
+    lang: Option<&str>,
+) -> anyhow::Result<Bible> {
+    let translation = registry.select(bible, lang, &config.bible)?;
+    cache::load_bible(&require_bible(config, translation, registry)?)
 }
 
 #[cfg(test)]
@@ -209,18 +179,63 @@ mod tests {
     #[test]
     #[ignore]
     fn today_gen_test() {
-        let config = Config::default();
+        let bible =
+            setup_bible(&Config::default(), &Registry::load().unwrap(), None, None).unwrap();
 
         let year = 2022;
         let month = 3;
         for day in 1..31 {
             let seed = year + month + day;
             let mut rng = ChaCha8Rng::seed_from_u64(seed);
-            let (_title, chpter_num, vs_num, vs) = generate_verse_of_day(&config, &mut rng)
+            let (_title, chpter_num, vs_num, vs) = generate_verse_of_day(&bible, &mut rng)
                 .unwrap_or_else(|_| panic!("error on day {}", day));
             assert!(chpter_num >= 1);
-            assert!(vs_num >= 1);
+            assert!(vs_num.start >= 1);
             assert!(!vs.contents.is_empty());
         }
     }
+
+    const SAMPLE: &str = include_str!("../../tests/fixtures/sample.osis.xml");
+
+    #[test]
+    fn verse_of_the_day() {
+        let mut bible = crate::bible::format::parse_bible(SAMPLE).unwrap();
+        //the first verse of every paragraph with more than one is the rest of the one before
+        for book in &mut bible.books {
+            for chpt in &mut book.chapters {
+                for pgh in chpt.paragraphs.iter_mut().filter(|p| p.verses.len() > 1) {
+                    pgh.verses[0].number = VerseId::new(0);
+                }
+            }
+        }
+        for seed in 0..50 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let (_, _, vs_num, _) = generate_verse_of_day(&bible, &mut rng).unwrap();
+            assert!(!vs_num.is_partial());
+        }
+    }
+
+    #[test]
+    fn nothing_to_pick_from() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut bible = crate::bible::format::parse_bible(SAMPLE).unwrap();
+        for book in &mut bible.books {
+            for chpt in &mut book.chapters {
+                chpt.paragraphs
+                    .retain(|p| p.verses.iter().all(|v| v.number.is_partial()));
+            }
+        }
+        let err = generate_verse_of_day(&bible, &mut rng).err().unwrap();
+        assert!(err.to_string().starts_with("there are no verses in"));
+
+        for book in &mut bible.books {
+            book.chapters.clear();
+        }
+        let err = generate_verse_of_day(&bible, &mut rng).err().unwrap();
+        assert!(err.to_string().starts_with("there are no chapters in"));
+
+        bible.books.clear();
+        let err = generate_verse_of_day(&bible, &mut rng).err().unwrap();
+        assert_eq!(err.to_string(), "there are no books to pick from");
+    }
 }
@@ -0,0 +1,548 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+
+use crate::config::DownloadConfig;
+use crate::Config;
+
+///a catalog can point at zips on this computer as well (ie. a mirror on a USB stick)
+const FILE_SCHEME: &str = "file://";
+const HTTP_SCHEMES: &[&str] = &["http://", "https://"];
+///how long to wait before trying a download again the first time
+const BACKOFF: Duration = Duration::from_secs(1);
+const BAR_WIDTH: u64 = 20;
+///kept next to a download that's under way, what the server said the file was when it started
+const VALIDATOR_EXT: &str = ".validator";
+
+///Somewhere Bibles can be downloaded from
+pub trait Fetcher {
+    ///Download a URL into a file, carrying on from what's already in it when that's possible
+    fn fetch(&self, url: &str, dest: &Path) -> anyhow::Result<()>;
+}
+
+///the name of the file at the end of a URL (ie. engnet_usfx.zip)
+fn file_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+///where the validator of a download is kept (ie. .net.part.validator)
+fn validator_path(dest: &Path) -> PathBuf {
+    let mut path = dest.as_os_str().to_owned();
+    path.push(VALIDATOR_EXT);
+    PathBuf::from(path)
+}
+
+///What tells one version of a file from another for If-Range, a strong ETag or failing that
+///when it was last modified
+fn validator(headers: &HeaderMap) -> Option<&str> {
+    let etag = headers
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .filter(|tag| !tag.starts_with("W/"));
+    etag.or_else(|| headers.get(LAST_MODIFIED).and_then(|v| v.to_str().ok()))
+}
+
+///Downloads over HTTP, resuming with a range request when some of it came down already and
+///trying again when the connection is flaky
+pub struct HttpFetcher {
+    client: Client,
+    retries: u32,
+    ///how long to wait before the first retry, it doubles after that
+    backoff: Duration,
+}
+
+///A response we shouldn't keep asking for (ie. 404) or one worth trying again later (ie. 503)
+#[derive(Debug)]
+struct BadStatus(StatusCode);
+
+impl fmt::Display for BadStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the server said {}", self.0)
+    }
+}
+
+impl std::error::Error for BadStatus {}
+
+///Something went wrong on this machine rather than over the network (ie. the disk is full),
+///trying the download again won't help
+#[derive(Debug)]
+struct LocalFile(PathBuf, io::Error);
+
+impl fmt::Display for LocalFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot write {}", self.0.display())
+    }
+}
+
+impl std::error::Error for LocalFile {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.1)
+    }
+}
+
+///marks a failed write to `path` as one that isn't worth retrying
+fn local<T>(path: &Path, result: io::Result<T>) -> Result<T, LocalFile> {
+    result.map_err(|err| LocalFile(path.to_path_buf(), err))
+}
+
+///is a failed download worth trying again
+fn is_transient(err: &anyhow::Error) -> bool {
+    if err.chain().any(|cause| cause.is::<LocalFile>()) {
+        return false;
+    }
+    err.chain().any(|cause| {
+        if let Some(BadStatus(status)) = cause.downcast_ref::<BadStatus>() {
+            status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+        } else if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            err.is_timeout() || err.is_connect() || err.is_body() || err.is_request()
+        } else {
+            cause.is::<io::Error>()
+        }
+    })
+}
+
+impl HttpFetcher {
+    pub fn new(config: &DownloadConfig) -> anyhow::Result<Self> {
+        let mut builder = Client::builder()
+            .connect_timeout(Duration::from_secs(config.connect_timeout))
+            .timeout(Duration::from_secs(config.read_timeout));
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy.as_str())
+                    .context(format!("{} is not a proxy kyro can use", proxy))?,
+            );
+        }
+        let client = builder.build().context("cannot set up the HTTP client")?;
+        Ok(Self {
+            client,
+            retries: config.retries,
+            backoff: BACKOFF,
+        })
+    }
+
+    #[cfg(test)]
+    fn with_backoff(self, backoff: Duration) -> Self {
+        Self { backoff, ..self }
+    }
+
+    ///One go at downloading the rest of a URL. What's already there is only carried on from
+    ///when the server says the file is still the one it came from, otherwise it starts over.
+    fn fetch_once(&self, url: &str, dest: &Path) -> anyhow::Result<()> {
+        let have = fs::metadata(dest).map(|m| m.len()).unwrap_or_default();
+        let saved = fs::read_to_string(validator_path(dest)).ok();
+        let mut request = self.client.get(url);
+        if let Some(saved) = saved.as_deref().filter(|_| have > 0) {
+            request = request
+                .header(RANGE, format!("bytes={}-", have))
+                .header(IF_RANGE, saved);
+        }
+        let mut response = request.send().context(format!("cannot download {}", url))?;
+        let (mut file, done): (File, u64) = match response.status() {
+            StatusCode::PARTIAL_CONTENT => (
+                local(dest, OpenOptions::new().append(true).open(dest))?,
+                have,
+            ),
+            //there's nothing past what we have so it must have all come down last time
+            StatusCode::RANGE_NOT_SATISFIABLE if have > 0 => {
+                let _ = fs::remove_file(validator_path(dest));
+                return Ok(());
+            }
+            status if status.is_success() => {
+                match validator(response.headers()) {
+                    Some(validator) => {
+                        let path = validator_path(dest);
+                        local(&path, fs::write(&path, validator))?
+                    }
+                    None => {
+                        let _ = fs::remove_file(validator_path(dest));
+                    }
+                }
+                (local(dest, File::create(dest))?, 0)
+            }
+            status => {
+                return Err(anyhow::Error::new(BadStatus(status)))
+                    .context(format!("cannot download {}", url))
+            }
+        };
+
+        let total = response.content_length().map(|len| len + done);
+        let mut progress = Progress::new(file_name(url), done, total);
+        let mut buf = [0; 64 * 1024];
+        loop {
+            let n = response
+                .read(&mut buf)
+                .context(format!("the download of {} was cut off", url))?;
+            if n == 0 {
+                break;
+            }
+            local(dest, file.write_all(&buf[..n]))?;
+            progress.add(n as u64);
+        }
+        progress.finish();
+        let _ = fs::remove_file(validator_path(dest));
+        Ok(())
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, url: &str, dest: &Path) -> anyhow::Result<()> {
+        let mut wait = self.backoff;
+        let mut attempt = 0;
+        loop {
+            match self.fetch_once(url, dest) {
+                Err(err) if attempt < self.retries && is_transient(&err) => {
+                    //whatever came down stays in dest so the next try carries on from there
+                    attempt += 1;
+                    thread::sleep(wait);
+                    wait *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+///A progress bar for a download on stderr, only drawn when stderr is a terminal
+struct Progress<'a> {
+    name: &'a str,
+    done: u64,
+    total: Option<u64>,
+    draw: bool,
+}
+
+impl<'a> Progress<'a> {
+    fn new(name: &'a str, done: u64, total: Option<u64>) -> Self {
+        Self {
+            name,
+            done,
+            total,
+            draw: io::stderr().is_terminal(),
+        }
+    }
+
+    fn add(&mut self, n: u64) {
+        self.done += n;
+        if self.draw {
+            eprint!("\r{}", progress_line(self.name, self.done, self.total));
+        }
+    }
+
+    fn finish(&self) {
+        if self.draw {
+            eprintln!();
+        }
+    }
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+}
+
+///(ie. engnet_usfx.zip [##########----------]  50% 1.2 MB/2.4 MB)
+fn progress_line(name: &str, done: u64, total: Option<u64>) -> String {
+    match total {
+        Some(total) if total > 0 => {
+            let filled = (done.min(total) * BAR_WIDTH / total) as usize;
+            format!(
+                "{} [{}{}] {:>3}% {}/{}",
+                name,
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH as usize - filled),
+                done.min(total) * 100 / total,
+                megabytes(done),
+                megabytes(total)
+            )
+        }
+        _ => format!("{} {}", name, megabytes(done)),
+    }
+}
+
+///Copies `file://` URLs
+pub struct FileFetcher;
+
+impl Fetcher for FileFetcher {
+    fn fetch(&self, url: &str, dest: &Path) -> anyhow::Result<()> {
+        let path = url
+            .strip_prefix(FILE_SCHEME)
+            .context(format!("{} is not a file:// URL", url))?;
+        fs::copy(path, dest).context(format!("cannot read {}", path))?;
+        Ok(())
+    }
+}
+
+///A directory with copies of the zips in it (ie. a network share), they're found by the file
+///name at the end of the URL
+pub struct DirMirror {
+    dir: PathBuf,
+}
+
+impl DirMirror {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl Fetcher for DirMirror {
+    fn fetch(&self, url: &str, dest: &Path) -> anyhow::Result<()> {
+        let path = self.dir.join(file_name(url));
+        anyhow::ensure!(path.is_file(), "{} is not in the mirror", path.display());
+        fs::copy(&path, dest).context(format!("cannot read {}", path.display()))?;
+        Ok(())
+    }
+}
+
+///Tries each of the mirrors in the config before going to where a Bible really comes from
+pub struct Fetchers {
+    http: HttpFetcher,
+    mirrors: Vec<String>,
+}
+
+impl Fetchers {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        Ok(Self {
+            http: HttpFetcher::new(&config.download)?,
+            mirrors: config.mirrors.clone(),
+        })
+    }
+
+    ///fetch a URL with the fetcher for its scheme
+    fn fetch_from(&self, url: &str, dest: &Path) -> anyhow::Result<()> {
+        if HTTP_SCHEMES.iter().any(|s| url.starts_with(s)) {
+            self.http.fetch(url, dest)
+        } else if url.starts_with(FILE_SCHEME) {
+            FileFetcher.fetch(url, dest)
+        } else {
+            anyhow::bail!("kyro doesn't know how to download {}", url)
+        }
+    }
+}
+
+impl Fetcher for Fetchers {
+    fn fetch(&self, url: &str, dest: &Path) -> anyhow::Result<()> {
+        let name = file_name(url);
+        for mirror in &self.mirrors {
+            let result = if mirror.contains("://") {
+                self.fetch_from(&format!("{}/{}", mirror.trim_end_matches('/'), name), dest)
+            } else {
+                DirMirror::new(mirror).fetch(url, dest)
+            };
+            //a mirror that doesn't have it is fine, the next one might
+            if result.is_ok() {
+                return Ok(());
+            }
+        }
+        self.fetch_from(url, dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{etag, Behaviour, TestServer};
+
+    fn fetchers(mirrors: &[String]) -> Fetchers {
+        let config = Config {
+            mirrors: mirrors.to_vec(),
+            ..Default::default()
+        };
+        Fetchers::new(&config).unwrap()
+    }
+
+    fn http() -> HttpFetcher {
+        HttpFetcher::new(&DownloadConfig::default())
+            .unwrap()
+            .with_backoff(Duration::from_millis(1))
+    }
+
+    #[test]
+    fn fetch_over_http() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let server = TestServer::start(&[("/a.zip", b"zipped".to_vec())], Behaviour::default());
+        let dest = dir.path().join("a.zip");
+
+        http().fetch(&server.url_of("/a.zip"), &dest)?;
+        assert_eq!(fs::read(&dest)?, b"zipped");
+        assert!(http().fetch(&server.url_of("/missing.zip"), &dest).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_files() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mirror = dir.path().join("mirror");
+        fs::create_dir(&mirror)?;
+        fs::write(mirror.join("a.zip"), "from the mirror")?;
+        let dest = dir.path().join("dest.zip");
+
+        let url = format!("file://{}", mirror.join("a.zip").display());
+        FileFetcher.fetch(&url, &dest)?;
+        assert_eq!(fs::read_to_string(&dest)?, "from the mirror");
+        assert!(FileFetcher
+            .fetch("https://ebible.org/a.zip", &dest)
+            .is_err());
+
+        let dir_mirror = DirMirror::new(&mirror);
+        dir_mirror.fetch("https://ebible.org/Scriptures/a.zip", &dest)?;
+        assert_eq!(fs::read_to_string(&dest)?, "from the mirror");
+        assert!(dir_mirror
+            .fetch("https://ebible.org/Scriptures/b.zip", &dest)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn mirrors_come_first() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let server = TestServer::start(
+            &[
+                ("/mirror/a.zip", b"mirrored".to_vec()),
+                ("/upstream/a.zip", b"upstream".to_vec()),
+                ("/upstream/b.zip", b"only upstream".to_vec()),
+            ],
+            Behaviour::default(),
+        );
+        let empty = dir.path().join("empty");
+        fs::create_dir(&empty)?;
+        let fetcher = fetchers(&[
+            empty.to_string_lossy().into_owned(),
+            server.url_of("/mirror/"),
+        ]);
+        let (a, b) = (dir.path().join("a.zip"), dir.path().join("b.zip"));
+
+        fetcher.fetch(&server.url_of("/upstream/a.zip"), &a)?;
+        assert_eq!(fs::read(&a)?, b"mirrored");
+        fetcher.fetch(&server.url_of("/upstream/b.zip"), &b)?;
+        assert_eq!(fs::read(&b)?, b"only upstream");
+        assert!(fetcher
+            .fetch("ftp://example.com/c.zip", &dir.path().join("c.zip"))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn flaky_downloads_are_retried() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let body: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let server = TestServer::start(
+            &[("/a.zip", body.clone())],
+            Behaviour {
+                cut_off_after: Some(100),
+                ..Default::default()
+            },
+        );
+        let dest = dir.path().join("a.zip");
+
+        http().fetch(&server.url_of("/a.zip"), &dest)?;
+        assert_eq!(fs::read(&dest)?, body);
+        let ranges: Vec<Option<String>> = server.requests().into_iter().map(|r| r.range).collect();
+        assert_eq!(ranges, vec![None, Some("bytes=100-".to_string())]);
+        assert_eq!(server.requests()[1].if_range, Some(etag(&body)));
+        assert!(!validator_path(&dest).exists());
+
+        //a file that isn't there won't be there the next time either
+        assert!(http()
+            .fetch(&server.url_of("/missing.zip"), &dir.path().join("b.zip"))
+            .is_err());
+        assert_eq!(server.requests().len(), 3);
+
+        //and neither will somewhere it can't be saved to
+        let nowhere = dir.path().join("missing").join("a.zip");
+        let err = http()
+            .fetch(&server.url_of("/a.zip"), &nowhere)
+            .unwrap_err();
+        assert!(!is_transient(&err));
+        assert_eq!(server.requests().len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn changed_files_start_over() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let server =
+            TestServer::start(&[("/a.zip", b"the new zip".to_vec())], Behaviour::default());
+        let dest = dir.path().join("a.zip");
+
+        //part of an older version of the file
+        fs::write(&dest, "the old")?;
+        fs::write(validator_path(&dest), "\"old\"")?;
+        http().fetch(&server.url_of("/a.zip"), &dest)?;
+        assert_eq!(fs::read(&dest)?, b"the new zip");
+        assert_eq!(server.requests()[0].if_range.as_deref(), Some("\"old\""));
+
+        //and nothing can be resumed without knowing which version it's from
+        fs::write(&dest, "the old")?;
+        http().fetch(&server.url_of("/a.zip"), &dest)?;
+        assert_eq!(fs::read(&dest)?, b"the new zip");
+        assert_eq!(server.requests()[1].range, None);
+        Ok(())
+    }
+
+    #[test]
+    fn slow_servers_time_out() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let server = TestServer::start(
+            &[("/a.zip", b"zipped".to_vec())],
+            Behaviour {
+                stall_first: true,
+                ..Default::default()
+            },
+        );
+        let config = DownloadConfig {
+            read_timeout: 1,
+            retries: 0,
+            ..Default::default()
+        };
+        let fetcher = HttpFetcher::new(&config)?;
+        let dest = dir.path().join("a.zip");
+
+        assert!(fetcher.fetch(&server.url_of("/a.zip"), &dest).is_err());
+        fetcher.fetch(&server.url_of("/a.zip"), &dest)?;
+        assert_eq!(fs::read(&dest)?, b"zipped");
+        Ok(())
+    }
+
+    #[test]
+    fn downloads_go_through_the_proxy() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        //a proxy is asked for the whole URL
+        let server = TestServer::start(
+            &[("http://ebible.invalid/a.zip", b"proxied".to_vec())],
+            Behaviour::default(),
+        );
+        let config = DownloadConfig {
+            proxy: Some(server.url.clone()),
+            ..Default::default()
+        };
+        let dest = dir.path().join("a.zip");
+
+        HttpFetcher::new(&config)?.fetch("http://ebible.invalid/a.zip", &dest)?;
+        assert_eq!(fs::read(&dest)?, b"proxied");
+        assert!(HttpFetcher::new(&DownloadConfig {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn progress_bar() {
+        assert_eq!(
+            progress_line("a.zip", 1_200_000, Some(2_400_000)),
+            "a.zip [##########----------]  50% 1.2 MB/2.4 MB"
+        );
+        assert_eq!(
+            progress_line("a.zip", 2_400_000, Some(2_400_000)),
+            "a.zip [####################] 100% 2.4 MB/2.4 MB"
+        );
+        assert_eq!(progress_line("a.zip", 300_000, None), "a.zip 0.3 MB");
+    }
+}
@@ -0,0 +1,308 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use crate::bible::format;
+use crate::cache;
+use crate::registry::{add_to_manifest, check_id, user_manifest_path, Registry, Translation};
+use crate::storage::get_data_dir;
+
+const ZIP_EXT: &str = "zip";
+const STAGING_PREFIX: &str = ".import-";
+///the language code of a Bible that doesn't say what it's in, or not in a way we can use
+const UNKNOWN_LANGUAGE: &str = "und";
+
+///What to call an imported Bible and where to keep it
+#[derive(Debug, Default)]
+pub struct ImportOpts {
+    ///the name to pick it with (ie. `--bible myversion`), the file name when not given
+    pub name: Option<String>,
+    ///the code of the language to file it under (ie. eng), worked out from the Bible when not
+    ///given
+    pub language: Option<String>,
+}
+
+fn is_zip(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(ZIP_EXT))
+}
+
+///The name of an import from the file it came from, up to the first `.` and with anything that
+///can't be in an id as `-` (ie. engkjv_usfx.zip -> engkjv_usfx, MyBible v2.osis.xml -> mybible-v2)
+fn default_name(path: &Path) -> anyhow::Result<String> {
+    let file_name = path
+        .file_name()
+        .map(|s| s.to_string_lossy())
+        .context(format!("cannot make a name from {}", path.display()))?;
+    let stem = file_name.split('.').next().unwrap_or_default();
+    Ok(stem
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '_' => c,
+            _ => '-',
+        })
+        .collect())
+}
+
+///the Bible in a directory or in one of the directories right inside of it, archives often
+///keep everything in a folder of their own
+fn find_source_within(dir: &Path) -> Option<PathBuf> {
+    if let Some(source) = format::find_source(dir) {
+        return Some(source);
+    }
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort();
+    dirs.iter().find_map(|d| format::find_source(d))
+}
+
+fn copy_source(source: &Path, dest_dir: &Path) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(dest_dir).context(format!("cannot create {}", dest_dir.display()))?;
+    if source.is_dir() {
+        for file in format::book_files(source)? {
+            fs::copy(&file, dest_dir.join(file.file_name().unwrap_or_default()))
+                .context(format!("cannot copy {}", file.display()))?;
+        }
+        return Ok(dest_dir.to_path_buf());
+    }
+
+    let dest = dest_dir.join(source.file_name().unwrap_or_default());
+    fs::copy(source, &dest).context(format!("cannot copy {}", source.display()))?;
+    Ok(dest)
+}
+
+///Check that a file, directory of books or zip holds a Bible we can read and copy it into the
+///data dir (ie. $HOME/.local/share/kyro/English/myversion/)
+pub fn import_into(
+    data_dir: &Path,
+    path: &Path,
+    opts: &ImportOpts,
+    registry: &Registry,
+) -> anyhow::Result<Translation> {
+    anyhow::ensure!(path.exists(), "{} does not exist", path.display());
+    let name = match &opts.name {
+        Some(name) => name.clone(),
+        None => default_name(path)?,
+    };
+    //both end up in paths so they're checked before anything is written
+    check_id("name", &name).context("pick another name with --name")?;
+    if let Some(language) = &opts.language {
+        check_id("language code", language)?;
+    }
+    anyhow::ensure!(
+        registry.find(&name).is_none(),
+        "there is already a Bible called {}, pick another with --name",
+        name
+    );
+    fs::create_dir_all(data_dir).context("cannot create the data dir")?;
+
+    //a zip is unpacked to the side first so a bad one never ends up in the data dir
+    let staging = data_dir.join(format!("{}{}", STAGING_PREFIX, name));
+    let result = (|| {
+        let source = if is_zip(path) {
+            if staging.exists() {
+                fs::remove_dir_all(&staging)?;
+            }
+            let zipped = File::open(path).context("cannot open zip file")?;
+            zip::ZipArchive::new(zipped)
+                .context("cannot make an archive reader")?
+                .extract(&staging)
+                .context("cannot extract the files")?;
+            find_source_within(&staging).context(format!(
+                "there is no Bible kyro can read in {}",
+                path.display()
+            ))?
+        } else {
+            path.to_path_buf()
+        };
+
+        let bible = format::read_bible(&source)
+            .context(format!("{} is not a Bible kyro can read", path.display()))?;
+        anyhow::ensure!(
+            !bible.books.is_empty(),
+            "there are no books of the Bible in {}",
+            path.display()
+        );
+
+        let language = opts
+            .language
+            .clone()
+            .or_else(|| {
+                let language = bible.metadata.language.as_ref()?.to_ascii_lowercase();
+                check_id("language code", &language).ok()?;
+                Some(language)
+            })
+            .unwrap_or_else(|| UNKNOWN_LANGUAGE.to_string());
+        let mut translation = Translation {
+            id: name.clone(),
+            language,
+            name: bible.metadata.title.clone().unwrap_or_else(|| name.clone()),
+            format: format::source_format(&source)?.name().to_ascii_lowercase(),
+            source: String::new(),
+            checksum: None,
+        };
+        let dest_dir = registry.translation_dir(data_dir, &translation);
+        anyhow::ensure!(
+            !dest_dir.exists(),
+            "{} is already taken, pick another name with --name",
+            dest_dir.display()
+        );
+
+        let dest = copy_source(&source, &dest_dir)?;
+        //compile it now so that the first search is as quick as the rest
+        cache::build_cache(&dest)?;
+        translation.source = dest.to_string_lossy().into_owned();
+        Ok(translation)
+    })();
+
+    if staging.exists() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+///import a Bible into kyro's data dir and add it to the user's translations
+pub fn import_bible(path: &Path, opts: &ImportOpts, registry: &Registry) -> anyhow::Result<()> {
+    let data_dir = get_data_dir().context("couldn't determine data dir path")?;
+    let translation = import_into(&data_dir, path, opts, registry)?;
+    println!(
+        "imported {} into {}, use it with --bible {}",
+        path.display(),
+        translation.source,
+        translation.id
+    );
+    add_to_manifest(&user_manifest_path()?, translation)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    fn registry() -> Registry {
+        Registry::load().unwrap()
+    }
+
+    fn import(data_dir: &Path, path: &Path, name: &str) -> anyhow::Result<Translation> {
+        let opts = ImportOpts {
+            name: Some(name.to_string()),
+            language: None,
+        };
+        import_into(data_dir, path, &opts, &registry())
+    }
+
+    #[test]
+    fn import_a_file() -> anyhow::Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let imported = import(data_dir.path(), &fixture("sample.osis.xml"), "sample")?;
+
+        let path = data_dir.path().join("English/sample/sample.osis.xml");
+        assert_eq!(imported.language, "en");
+        assert_eq!(imported.format, "osis");
+        assert_eq!(imported.source, path.to_string_lossy());
+        assert!(imported.is_local());
+        assert!(cache::cache_path(&path).is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn import_a_zip() -> anyhow::Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let zip_path = data_dir.path().join("draft.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path)?);
+        for file in format::book_files(&fixture("usfm"))? {
+            let name = file.file_name().unwrap().to_string_lossy();
+            zip.start_file(format!("draft/{}", name), Default::default())?;
+            zip.write_all(&fs::read(&file)?)?;
+        }
+        zip.finish()?;
+
+        let opts = ImportOpts {
+            name: None,
+            language: Some("eng".to_string()),
+        };
+        let imported = import_into(data_dir.path(), &zip_path, &opts, &registry())?;
+        assert_eq!(imported.id, "draft");
+        assert_eq!(imported.format, "usfm");
+        let path = data_dir.path().join("English/draft");
+        assert_eq!(imported.source, path.to_string_lossy());
+        assert_eq!(format::read_bible(&path)?.books.len(), 2);
+        assert!(!data_dir.path().join(".import-draft").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn bad_imports_are_refused() -> anyhow::Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let not_a_bible = data_dir.path().join("notes.xml");
+        fs::write(&not_a_bible, "<html></html>")?;
+        assert!(import(data_dir.path(), &not_a_bible, "notes").is_err());
+        assert!(!data_dir.path().join("Unknown/notes").exists());
+
+        import(data_dir.path(), &fixture("sample.zefania.xml"), "sample")?;
+        assert!(import(data_dir.path(), &fixture("sample.zefania.xml"), "sample").is_err());
+
+        //the name of a translation kyro already knows
+        assert!(import(data_dir.path(), &fixture("sample.osis.xml"), "net").is_err());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("sample.osis.xml", "sample")]
+    #[case("engkjv_usfx.zip", "engkjv_usfx")]
+    #[case("MyBible v2.xml", "mybible-v2")]
+    fn names_from_files(#[case] file: &str, #[case] name: &str) {
+        assert_eq!(default_name(Path::new(file)).unwrap(), name);
+        assert!(check_id("name", name).is_ok());
+    }
+
+    #[test]
+    fn import_without_a_name() -> anyhow::Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let imported = import_into(
+            data_dir.path(),
+            &fixture("sample.osis.xml"),
+            &ImportOpts::default(),
+            &registry(),
+        )?;
+        assert_eq!(imported.id, "sample");
+        assert!(data_dir.path().join("English/sample").is_dir());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(Some(".."), None)]
+    #[case(Some("."), None)]
+    #[case(Some("../../elsewhere"), None)]
+    #[case(Some("My Bible"), None)]
+    #[case(Some("sample"), Some(".."))]
+    #[case(Some("sample"), Some("English/../.."))]
+    fn names_that_are_not_ids(#[case] name: Option<&str>, #[case] language: Option<&str>) {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("kyro");
+        let opts = ImportOpts {
+            name: name.map(String::from),
+            language: language.map(String::from),
+        };
+        let result = import_into(&data_dir, &fixture("sample.osis.xml"), &opts, &registry());
+        assert!(result.is_err());
+        //nothing at all is written
+        assert!(!data_dir.exists());
+    }
+}
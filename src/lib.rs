@@ -1,9 +1,18 @@
-mod bible;
+pub mod bible;
+mod bibles;
+mod cache;
 mod cli;
 mod config;
+mod fetch;
+mod import;
+mod registry;
 mod storage;
+#[cfg(test)]
+mod test_server;
 
 pub use crate::storage::*;
+pub use bible::Bible;
 pub use cli::query::Query;
-pub use cli::Command;
+pub use cli::{Command, Opts};
 pub use config::Config;
+pub use registry::Registry;
@@ -1,9 +1,12 @@
-use kyro::{download_bible, Command, Config};
+use kyro::{Config, Opts, Registry};
 use structopt::StructOpt;
 
 fn main() -> anyhow::Result<()> {
+    //parse the arguments first so --help and typos never need the config or a Bible
+    let opts = Opts::from_args();
     let config = Config::get_config()?;
-    download_bible(&config)?;
-    Command::from_args().run(&config)?;
+    let registry = Registry::load()?;
+    kyro::move_legacy_installs(&registry)?;
+    opts.run(&config, &registry)?;
     Ok(())
 }
@@ -0,0 +1,409 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::get_config_dir;
+
+///the translations that ship with kyro
+const BUNDLED_MANIFEST: &str = include_str!("../translations.toml");
+///translations the user has added, kept in the config dir
+const USER_MANIFEST: &str = "translations.toml";
+
+///Everything kyro needs to know to get a translation and find it again
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Translation {
+    ///what it's picked with (ie. `--bible net`)
+    pub id: String,
+    ///the language code of the translation (ie. eng)
+    pub language: String,
+    ///the name to show people (ie. New English Translation)
+    pub name: String,
+    ///the format the Bible is in (ie. usfx)
+    pub format: String,
+    ///a URL to download a zip of it from or the path to it on this computer
+    pub source: String,
+    ///the sha256 of the download in hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+impl Translation {
+    ///is the source a Bible already on this computer rather than a URL to download a zip from
+    pub fn is_local(&self) -> bool {
+        !self.source.contains("://")
+    }
+
+    ///the name the download is saved under (ie. engnet_usfx.zip)
+    pub fn file_name(&self) -> &str {
+        self.source
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.id)
+    }
+}
+
+///The layout of a manifest file
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Manifest {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    languages: BTreeMap<String, String>,
+    #[serde(default, rename = "translation")]
+    translations: Vec<Translation>,
+}
+
+impl Manifest {
+    fn parse(contents: &str) -> anyhow::Result<Self> {
+        let manifest: Manifest = toml::from_str(contents)?;
+        manifest.check()?;
+        Ok(manifest)
+    }
+
+    ///the ids and languages end up in paths in the data dir so none of them can lead out of it
+    fn check(&self) -> anyhow::Result<()> {
+        for (code, name) in &self.languages {
+            check_id("language code", code)?;
+            anyhow::ensure!(
+                !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':']),
+                "{} is not a valid language name",
+                name
+            );
+        }
+        for translation in &self.translations {
+            check_id("translation id", &translation.id)?;
+            check_id("language code", &translation.language)?;
+        }
+        Ok(())
+    }
+
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        if !path.is_file() {
+            return Ok(Manifest::default());
+        }
+        let contents =
+            fs::read_to_string(path).context(format!("cannot read {}", path.display()))?;
+        Manifest::parse(&contents).context(format!("{} is not a valid manifest", path.display()))
+    }
+}
+
+///All the translations kyro knows about
+#[derive(Debug, Default)]
+pub struct Registry {
+    ///the name of each language by its code (ie. eng -> English)
+    languages: BTreeMap<String, String>,
+    translations: Vec<Translation>,
+}
+
+impl Registry {
+    ///Build a registry from the contents of a few manifests, later ones add to and replace what's
+    ///in the earlier ones
+    pub fn from_manifests(manifests: &[&str]) -> anyhow::Result<Self> {
+        let mut registry = Registry::default();
+        for contents in manifests {
+            registry.extend(Manifest::parse(contents)?);
+        }
+        Ok(registry)
+    }
+
+    ///the bundled translations and any the user has added
+    pub fn load() -> anyhow::Result<Self> {
+        Registry::from_manifests(&[BUNDLED_MANIFEST])
+            .context("the bundled translation manifest is broken")?
+            .with_user_additions()
+    }
+
+    ///the translations in a catalog other than the bundled one and any the user has added
+    pub fn with_catalog(path: &Path) -> anyhow::Result<Self> {
+        let contents =
+            fs::read_to_string(path).context(format!("cannot read {}", path.display()))?;
+        Registry::from_manifests(&[&contents])
+            .context(format!("{} is not a valid catalog", path.display()))?
+            .with_user_additions()
+    }
+
+    fn with_user_additions(mut self) -> anyhow::Result<Self> {
+        self.extend(Manifest::read(&user_manifest_path()?)?);
+        Ok(self)
+    }
+
+    fn extend(&mut self, manifest: Manifest) {
+        self.languages.extend(manifest.languages);
+        for translation in manifest.translations {
+            self.add(translation);
+        }
+    }
+
+    ///add a translation, replacing any with the same id
+    pub fn add(&mut self, translation: Translation) {
+        match self
+            .translations
+            .iter_mut()
+            .find(|t| t.id == translation.id)
+        {
+            Some(existing) => *existing = translation,
+            None => self.translations.push(translation),
+        }
+    }
+
+    pub fn translations(&self) -> &[Translation] {
+        &self.translations
+    }
+
+    pub fn find(&self, id: &str) -> Option<&Translation> {
+        self.translations
+            .iter()
+            .find(|t| t.id.eq_ignore_ascii_case(id))
+    }
+
+    ///like `find` but with an error that's fit to show the user
+    pub fn get(&self, id: &str) -> anyhow::Result<&Translation> {
+        self.find(id).with_context(|| {
+            let ids: Vec<&str> = self.translations.iter().map(|t| t.id.as_str()).collect();
+            format!(
+                "there is no Bible called {}, the ones kyro knows are: {}",
+                id,
+                ids.join(", ")
+            )
+        })
+    }
+
+    ///is a translation in a language, going by its code or its name (ie. fra or french)
+    pub fn in_language(&self, translation: &Translation, language: &str) -> bool {
+        translation.language.eq_ignore_ascii_case(language)
+            || self
+                .language_name(&translation.language)
+                .eq_ignore_ascii_case(language)
+    }
+
+    ///The translation to read from, `bible` and `language` are the ones asked for on the command
+    ///line and `default` the one in the config. Asking for just a language picks the default if
+    ///it's in that language, otherwise the first translation that is.
+    pub fn select(
+        &self,
+        bible: Option<&str>,
+        language: Option<&str>,
+        default: &str,
+    ) -> anyhow::Result<&Translation> {
+        let language = match language {
+            Some(language) => language,
+            None => return self.get(bible.unwrap_or(default)),
+        };
+        if let Some(id) = bible {
+            let translation = self.get(id)?;
+            anyhow::ensure!(
+                self.in_language(translation, language),
+                "{} is in {} not {}",
+                translation.id,
+                self.language_name(&translation.language),
+                language
+            );
+            return Ok(translation);
+        }
+
+        self.find(default)
+            .filter(|t| self.in_language(t, language))
+            .or_else(|| {
+                self.translations
+                    .iter()
+                    .find(|t| self.in_language(t, language))
+            })
+            .with_context(|| {
+                let mut languages: Vec<&str> = self
+                    .translations
+                    .iter()
+                    .map(|t| self.language_name(&t.language))
+                    .collect();
+                languages.sort_unstable();
+                languages.dedup();
+                format!(
+                    "there are no Bibles in {}, the languages kyro knows are: {}",
+                    language,
+                    languages.join(", ")
+                )
+            })
+    }
+
+    ///the name of a language for directories and messages, the code itself if we don't know it
+    pub fn language_name<'a>(&'a self, code: &'a str) -> &'a str {
+        self.languages
+            .iter()
+            .find(|(c, _)| c.eq_ignore_ascii_case(code))
+            .map(|(_, name)| name.as_str())
+            .unwrap_or(code)
+    }
+
+    ///where a translation is kept in the data dir (ie. $HOME/.local/share/kyro/English/net/)
+    pub fn translation_dir(&self, data_dir: &Path, translation: &Translation) -> PathBuf {
+        data_dir
+            .join(self.language_name(&translation.language))
+            .join(&translation.id)
+    }
+}
+
+///Make sure an id or language code is fit to be a directory name, only lower case letters,
+///digits, `-` and `_` are allowed so it can't be `..` or have a path in it
+pub fn check_id(what: &str, id: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_'),
+        "{} is not a valid {}, use only lower case letters, digits, - and _",
+        id,
+        what
+    );
+    Ok(())
+}
+
+///where the user's own translations are listed (ie. $HOME/.config/kyro/translations.toml)
+pub fn user_manifest_path() -> anyhow::Result<PathBuf> {
+    Ok(get_config_dir()
+        .context("couldn't determine config dir path")?
+        .join(USER_MANIFEST))
+}
+
+///add a translation to a manifest file, replacing any with the same id
+pub fn add_to_manifest(path: &Path, translation: Translation) -> anyhow::Result<()> {
+    let mut manifest = Manifest::read(path)?;
+    manifest.translations.retain(|t| t.id != translation.id);
+    manifest.translations.push(translation);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context(format!("cannot create {}", dir.display()))?;
+    }
+    fs::write(path, toml::to_string(&manifest)?)
+        .context(format!("cannot write {}", path.display()))?;
+    Ok(())
+}
+
+///take a translation out of a manifest file, returns whether it was in there
+pub fn remove_from_manifest(path: &Path, id: &str) -> anyhow::Result<bool> {
+    let mut manifest = Manifest::read(path)?;
+    let before = manifest.translations.len();
+    manifest
+        .translations
+        .retain(|t| !t.id.eq_ignore_ascii_case(id));
+    if manifest.translations.len() == before {
+        return Ok(false);
+    }
+    fs::write(path, toml::to_string(&manifest)?)
+        .context(format!("cannot write {}", path.display()))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const USER: &str = r#"
+[languages]
+fra = "French"
+
+[[translation]]
+id = "ost"
+language = "fra"
+name = "Ostervald"
+format = "zefania"
+source = "/home/me/bibles/ost.xml"
+
+[[translation]]
+id = "net"
+language = "eng"
+name = "NET Bible mirror"
+format = "usfx"
+source = "https://example.com/engnet_usfx.zip"
+checksum = "abc123"
+"#;
+
+    #[test]
+    fn bundled_manifest() {
+        let registry = Registry::from_manifests(&[BUNDLED_MANIFEST]).unwrap();
+        let net = registry.get("NET").unwrap();
+
+        assert_eq!(net.language, "eng");
+        assert_eq!(net.file_name(), "engnet_usfx.zip");
+        assert!(!net.is_local());
+        assert_eq!(registry.language_name("eng"), "English");
+    }
+
+    #[test]
+    fn ids() {
+        for id in ["net", "fra-lsg", "kjv_1769", "2024"] {
+            assert!(check_id("id", id).is_ok());
+        }
+        for id in ["", ".", "..", "../net", "en/net", "a\\b", "NET", "my bible"] {
+            assert!(check_id("id", id).is_err());
+        }
+    }
+
+    #[rstest]
+    #[case("[languages]\nen = \"..\"")]
+    #[case("[languages]\n\"../en\" = \"English\"")]
+    #[case("[[translation]]\nid = \"..\"\nlanguage = \"eng\"\nname = \"\"\nformat = \"usfx\"\nsource = \"\"")]
+    #[case("[[translation]]\nid = \"net\"\nlanguage = \"en/..\"\nname = \"\"\nformat = \"usfx\"\nsource = \"\"")]
+    fn paths_out_of_the_data_dir(#[case] manifest: &str) {
+        assert!(Registry::from_manifests(&[manifest]).is_err());
+    }
+
+    #[test]
+    fn user_additions() {
+        let registry = Registry::from_manifests(&[BUNDLED_MANIFEST, USER]).unwrap();
+
+        assert_eq!(registry.translations().len(), 3);
+        assert_eq!(registry.get("net").unwrap().name, "NET Bible mirror");
+        let ost = registry.get("ost").unwrap();
+        assert!(ost.is_local());
+        assert_eq!(
+            registry.translation_dir(Path::new("/data"), ost),
+            PathBuf::from("/data/French/ost")
+        );
+        assert_eq!(registry.language_name("deu"), "deu");
+
+        let err = registry.get("kjv").unwrap_err().to_string();
+        assert!(err.contains("net, lsg, ost"));
+    }
+
+    #[test]
+    fn select_a_translation() {
+        let registry = Registry::from_manifests(&[BUNDLED_MANIFEST, USER]).unwrap();
+        let select = |bible, language| registry.select(bible, language, "net").map(|t| &t.id);
+
+        assert_eq!(select(None, None).unwrap(), "net");
+        assert_eq!(select(Some("OST"), None).unwrap(), "ost");
+        assert_eq!(select(None, Some("french")).unwrap(), "lsg");
+        assert_eq!(select(None, Some("eng")).unwrap(), "net");
+        assert_eq!(select(Some("ost"), Some("fra")).unwrap(), "ost");
+        assert!(select(Some("missing"), None).is_err());
+        assert!(select(Some("net"), Some("french")).is_err());
+
+        let err = select(None, Some("german")).unwrap_err().to_string();
+        assert!(err.ends_with("English, French"));
+    }
+
+    #[test]
+    fn manifest_round_trip() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("kyro").join(USER_MANIFEST);
+        let mut ost = Registry::from_manifests(&[USER])?.get("ost")?.clone();
+
+        add_to_manifest(&path, ost.clone())?;
+        ost.name = "Ostervald 1996".to_string();
+        add_to_manifest(&path, ost)?;
+
+        let registry = Registry::from_manifests(&[&fs::read_to_string(&path)?])?;
+        assert_eq!(registry.translations().len(), 1);
+        assert_eq!(registry.get("ost")?.name, "Ostervald 1996");
+
+        assert!(remove_from_manifest(&path, "OST")?);
+        assert!(!remove_from_manifest(&path, "ost")?);
+        assert!(Registry::from_manifests(&[&fs::read_to_string(&path)?])?
+            .translations()
+            .is_empty());
+        Ok(())
+    }
+}
@@ -1,35 +1,28 @@
+use crate::bible::format;
+use crate::cache;
 pub use crate::config::Config;
-use crate::config::{BibleVersion, Language};
+use crate::fetch::{Fetcher, Fetchers};
+use crate::registry::{Registry, Translation};
 use anyhow::Context as _;
-use bytes::Bytes;
 use directories_next::ProjectDirs;
-use once_cell::sync::Lazy;
-use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::Write;
 use std::path::{Path, PathBuf};
 
-static CODES: Lazy<HashMap<Language, String>> = Lazy::new(|| {
-    let mut map = HashMap::new();
-    map.insert(Language::English, "eng".to_string());
-    map
-});
-
-const XML: &str = ".xml";
-const ZIP: &str = ".zip";
-const USFX: &str = "_usfx";
+const XML: &str = "xml";
+///the checksum of the zip a Bible was unpacked from, kept next to it
+const CHECKSUM_FILE: &str = ".checksum";
+///what a download is called until it's finished
+const PART_EXT: &str = ".part";
+///a download is unzipped in here before it's moved into place
+const STAGING_PREFIX: &str = ".download-";
+///the version dir being replaced by an update
+const OLD_PREFIX: &str = ".old-";
+///the translations older versions of kyro installed and the directory they used, which was
+///named after the version in the config rather than the id (ie. English/Net/)
+const LEGACY_DIRS: &[(&str, &str)] = &[("net", "Net")];
 const GITHUB_USER: &str = "BenGH28";
 const KYRO: &str = "kyro";
 
-///Get the raw github URL from the config
-pub fn get_bible_url(config: &Config) -> String {
-    match config.language {
-        Language::English => match config.version {
-            BibleVersion::Net => "https://ebible.org/Scriptures/engnet_usfx.zip".to_string(),
-        },
-    }
-}
-
 ///Get the directory to store all the Bibles
 pub fn get_data_dir() -> Option<PathBuf> {
     if let Some(proj_dirs) = ProjectDirs::from("", GITHUB_USER, KYRO) {
@@ -39,56 +32,241 @@ pub fn get_data_dir() -> Option<PathBuf> {
     None
 }
 
-///Download the Bible and save it on your computer
-pub fn download_bible(config: &Config) -> anyhow::Result<()> {
-    let url = get_bible_url(config);
-    save_to_pc(&url, config)?;
-    Ok(())
+///Get the directory the config is kept in, the same one confy uses (ie. $HOME/.config/kyro/)
+pub fn get_config_dir() -> Option<PathBuf> {
+    ProjectDirs::from("rs", "", KYRO).map(|proj_dirs| proj_dirs.config_dir().to_owned())
 }
 
-pub fn usfx_file(config: &Config, extension: &str) -> anyhow::Result<String> {
-    let lang = &config.language;
-    let version: String = config.version.to_string().to_ascii_lowercase();
+///Move the Bibles older versions of kyro installed to where they are looked for now
+pub fn move_legacy_installs(registry: &Registry) -> anyhow::Result<()> {
+    let data_dir: PathBuf = get_data_dir().context("couldn't determine data dir path")?;
+    move_legacy_installs_in(&data_dir, registry)
+}
+
+///Move the Bibles older versions of kyro installed in a data dir to where they are looked for
+///now (ie. English/Net/ -> English/net/), there's only something to do the first time
+pub fn move_legacy_installs_in(data_dir: &Path, registry: &Registry) -> anyhow::Result<()> {
+    for (id, legacy) in LEGACY_DIRS {
+        let translation = match registry.find(id) {
+            Some(translation) if !translation.is_local() => translation,
+            _ => continue,
+        };
+        let version_dir = registry.translation_dir(data_dir, translation);
+        let legacy_dir = version_dir.with_file_name(legacy);
+        //on a file system that ignores case they're already the same directory
+        if legacy_dir.is_dir() && !version_dir.exists() {
+            fs::rename(&legacy_dir, &version_dir).context(format!(
+                "cannot move {} to {}",
+                legacy_dir.display(),
+                version_dir.display()
+            ))?;
+        }
+    }
+    Ok(())
+}
 
-    let code: String = CODES
-        .get(lang)
-        .context("cannot find specified language")?
-        .to_string();
+///The path to a Bible that's on this computer. One that isn't is only downloaded when the config
+///says to, so nothing touches the network unless it's needed and wanted.
+pub fn require_bible(
+    config: &Config,
+    translation: &Translation,
+    registry: &Registry,
+) -> anyhow::Result<PathBuf> {
+    let data_dir: PathBuf = get_data_dir().context("couldn't determine data dir path")?;
+    require_bible_in(&data_dir, config, translation, registry)
+}
 
-    //engnet_usfx.xml | .zip
-    let fname = code + &version + USFX + extension;
-    Ok(fname)
+///like `require_bible` but in a particular data dir
+pub fn require_bible_in(
+    data_dir: &Path,
+    config: &Config,
+    translation: &Translation,
+    registry: &Registry,
+) -> anyhow::Result<PathBuf> {
+    let path = path_in(data_dir, translation, registry);
+    if path.exists() {
+        return Ok(path);
+    }
+    anyhow::ensure!(
+        !translation.is_local(),
+        "{} should be at {} but it isn't there anymore",
+        translation.id,
+        path.display()
+    );
+    anyhow::ensure!(
+        config.auto_download,
+        "{} isn't installed, run `kyro bibles install {}` to download it \
+         or set auto_download = true in the config",
+        translation.id,
+        translation.id
+    );
+    save_into(data_dir, translation, registry, &Fetchers::new(config)?)?;
+    Ok(path_in(data_dir, translation, registry))
 }
 
-///Save a Bible in xml to a file on the computer under the XDG format (ie. $HOME/.local/share/kyro/)
-///returns the path to the Bible xml
-pub fn save_to_pc(url: &str, config: &Config) -> anyhow::Result<()> {
+///Save a Bible to a file on the computer under the XDG format (ie. $HOME/.local/share/kyro/)
+pub fn save_to_pc(
+    translation: &Translation,
+    registry: &Registry,
+    fetcher: &dyn Fetcher,
+) -> anyhow::Result<()> {
     //get data_dir: $HOME/.local/share/kyro/
     let data_dir: PathBuf = get_data_dir().context("couldn't determine data dir path")?;
+    save_into(&data_dir, translation, registry, fetcher)
+}
 
-    //the full path to the extracted archive (ie. $HOME/.local/share/kyro/English/Net/)
-    let version_dir: PathBuf = data_dir
-        .join(config.language.to_string())
-        .join(config.version.to_string());
+///Download a Bible into a data dir unless it's already there
+pub fn save_into(
+    data_dir: &Path,
+    translation: &Translation,
+    registry: &Registry,
+    fetcher: &dyn Fetcher,
+) -> anyhow::Result<()> {
+    //the full path to the extracted archive (ie. $HOME/.local/share/kyro/English/net/)
+    let version_dir: PathBuf = registry.translation_dir(data_dir, translation);
 
-    //the full path to the extracted archive (ie. $HOME/.local/share/kyro/English/Net/engnet_usfx.xml)
-    let file_path: PathBuf = version_dir.join(usfx_file(config, XML)?);
+    //if there isn't a Bible in there yet then we can write it
+    if format::find_source(&version_dir).is_none() {
+        let (zip, sum) = download(data_dir, translation, fetcher)?;
+        install_zip(data_dir, &version_dir, translation, &zip, &sum)?;
+    }
+    Ok(())
+}
 
-    //the full path to the archive (ie. $HOME/.local/share/kyro/English/Net/engnet_usfx.zip)
-    let file_zip_path: PathBuf = version_dir.join(usfx_file(config, ZIP)?);
+///Download a Bible again and replace the one in the data dir if it has changed since, returns
+///whether it was replaced
+pub fn update_into(
+    data_dir: &Path,
+    translation: &Translation,
+    registry: &Registry,
+    fetcher: &dyn Fetcher,
+) -> anyhow::Result<bool> {
+    let version_dir: PathBuf = registry.translation_dir(data_dir, translation);
+    let installed = fs::read_to_string(version_dir.join(CHECKSUM_FILE)).ok();
+    let unchanged = |sum: &str| {
+        installed
+            .as_deref()
+            .is_some_and(|i| i.trim().eq_ignore_ascii_case(sum))
+    };
 
-    //if the directory doesn't exist then lets create it
-    if !version_dir.is_dir() {
-        fs::create_dir_all(&version_dir).context("cannot creat directory for storing Bibles")?;
+    //no need to download it to know it hasn't changed when the catalog has its checksum
+    if translation.checksum.as_deref().is_some_and(unchanged) {
+        return Ok(false);
     }
+    let (zip, sum) = download(data_dir, translation, fetcher)?;
+    if unchanged(&sum) {
+        fs::remove_file(&zip)?;
+        return Ok(false);
+    }
+    install_zip(data_dir, &version_dir, translation, &zip, &sum)?;
+    Ok(true)
+}
 
-    //if the file doesn't exist then we can write it
-    if !file_path.is_file() {
-        let bible_zip: Bytes = get_bible_zip(url)?;
-        let mut file: File =
-            File::create(&file_zip_path).context("could not write Bible to file")?;
-        file.write_all(&bible_zip)?;
-        unzip(&file_zip_path, &version_dir).context("unzipping has failed some how")?;
+///where a download is kept until it's finished (ie. $HOME/.local/share/kyro/.net.part)
+fn part_path(data_dir: &Path, translation: &Translation) -> PathBuf {
+    data_dir.join(format!(".{}{}", translation.id, PART_EXT))
+}
+
+///Download the zip of a Bible next to the data dir and check it against the checksum in the
+///catalog, returns where it is and its checksum
+fn download(
+    data_dir: &Path,
+    translation: &Translation,
+    fetcher: &dyn Fetcher,
+) -> anyhow::Result<(PathBuf, String)> {
+    fs::create_dir_all(data_dir).context("cannot create the data dir")?;
+    let part = part_path(data_dir, translation);
+    fetcher.fetch(&translation.source, &part)?;
+
+    let actual = cache::checksum_files(std::slice::from_ref(&part))?;
+    let expected = match &translation.checksum {
+        Some(expected) => expected,
+        None => {
+            //say so rather than quietly install something nobody checked
+            eprintln!(
+                "warning: {} has no checksum in the catalog so its download can't be verified, \
+                 add checksum = \"{}\" to its entry in translations.toml to pin this one",
+                translation.id, actual
+            );
+            return Ok((part, actual));
+        }
+    };
+    if !expected.eq_ignore_ascii_case(&actual) {
+        //start over next time rather than resume something that's wrong
+        fs::remove_file(&part)?;
+        anyhow::bail!(
+            "the download of {} is corrupt, its checksum is {} not {}",
+            translation.id,
+            actual,
+            expected
+        );
+    }
+    Ok((part, actual))
+}
+
+///Unzip a download to the side, check and compile the Bible in it, then swap it in for the
+///version dir in one go so there's never a half written Bible in there
+fn install_zip(
+    data_dir: &Path,
+    version_dir: &Path,
+    translation: &Translation,
+    zip: &Path,
+    sum: &str,
+) -> anyhow::Result<()> {
+    let staging = data_dir.join(format!("{}{}", STAGING_PREFIX, translation.id));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let result = (|| {
+        fs::create_dir_all(&staging).context("cannot create directory for storing Bibles")?;
+        unzip(zip, &staging).context("unzipping has failed some how")?;
+
+        //the archive can hold a Bible in any format we know so look for it rather than expect a
+        //name, then compile it now so that the first search is as quick as the rest
+        let source = format::find_source(&staging).context("the download has no Bible in it")?;
+        let found = format::source_format(&source)?.name();
+        anyhow::ensure!(
+            found.eq_ignore_ascii_case(&translation.format),
+            "{} should be {} but the download is {}",
+            translation.id,
+            translation.format,
+            found
+        );
+        cache::build_cache(&source)?;
+
+        //remember what was downloaded so `kyro bibles update` can tell when it changes
+        fs::write(staging.join(CHECKSUM_FILE), sum)?;
+        //the full path to the archive (ie. $HOME/.local/share/kyro/English/net/engnet_usfx.zip)
+        fs::rename(zip, staging.join(translation.file_name()))?;
+        replace_dir(&staging, version_dir)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+        let _ = fs::remove_file(zip);
+    }
+    result
+}
+
+///move a directory into place, the one that was there is only removed once the new one is in
+fn replace_dir(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).context(format!("cannot create {}", parent.display()))?;
+    }
+    let old = to.with_file_name(format!(
+        "{}{}",
+        OLD_PREFIX,
+        to.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    if to.exists() {
+        if old.exists() {
+            fs::remove_dir_all(&old)?;
+        }
+        fs::rename(to, &old).context(format!("cannot move {} aside", to.display()))?;
+    }
+    fs::rename(from, to).context(format!("cannot move the Bible to {}", to.display()))?;
+    if old.exists() {
+        fs::remove_dir_all(&old).context(format!("cannot remove {}", old.display()))?;
     }
     Ok(())
 }
@@ -97,10 +275,10 @@ fn unzip(zipped_file_path: &Path, dest_dir: &Path) -> anyhow::Result<()> {
     let zipped_file = File::open(zipped_file_path).context("cannot open zip file")?;
     let mut archive = zip::ZipArchive::new(zipped_file).context("cannot make an archive reader")?;
 
-    let result = archive
+    archive
         .extract(dest_dir)
         .context("cannot extract the files")?;
-    Ok(result)
+    Ok(())
 }
 
 pub fn bible_as_str(path: PathBuf) -> anyhow::Result<String> {
@@ -108,60 +286,269 @@ pub fn bible_as_str(path: PathBuf) -> anyhow::Result<String> {
     Ok(contents)
 }
 
-///get the absolute path to the bible xml file
-pub fn get_path_to_bible_file(config: &Config) -> anyhow::Result<PathBuf> {
+///get the absolute path to the Bible file, or the directory for a Bible with a file per book
+pub fn get_path_to_bible_file(
+    translation: &Translation,
+    registry: &Registry,
+) -> anyhow::Result<PathBuf> {
     let data_dir: PathBuf = get_data_dir().context("couldn't determine data dir path")?;
-    let file_path: PathBuf = data_dir
-        .join(&config.language.to_string())
-        .join(&config.version.to_string())
-        .join(usfx_file(config, XML)?);
-    Ok(file_path)
+    Ok(path_in(&data_dir, translation, registry))
 }
 
-///Get the text from the gratis-bible github account
-pub fn get_bible_zip(url: &str) -> anyhow::Result<Bytes> {
-    let bible: Bytes = reqwest::blocking::get(url)?.bytes()?;
-    Ok(bible)
+///where a Bible is in a data dir, or where the download goes when it isn't there yet
+pub fn path_in(data_dir: &Path, translation: &Translation, registry: &Registry) -> PathBuf {
+    if translation.is_local() {
+        return PathBuf::from(&translation.source);
+    }
+    let version_dir: PathBuf = registry.translation_dir(data_dir, translation);
+    //whatever format the Bible in there is in, or where the download goes when there isn't one
+    match format::find_source(&version_dir) {
+        Some(source) => source,
+        None => version_dir.join(Path::new(translation.file_name()).with_extension(XML)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const MYVERSION: &str = r#"
+[[translation]]
+id = "myversion"
+language = "eng"
+name = "My Version"
+format = "osis"
+source = "/kyro/English/myversion/myversion.xml"
+"#;
+
+    fn registry() -> Registry {
+        Registry::load().unwrap()
+    }
+
     #[test]
     fn test_get_bible_file_path() -> anyhow::Result<()> {
-        let actual_path = get_path_to_bible_file(&Config::default())?;
+        let registry = registry();
+        let actual_path = get_path_to_bible_file(registry.get("net")?, &registry)?;
         let data_dir: PathBuf = get_data_dir().context("couldn't determine data dir path")?;
-        let expected_path = data_dir.join("English").join("Net").join("engnet_usfx.xml");
+        let expected_path = data_dir.join("English").join("net").join("engnet_usfx.xml");
 
         assert_eq!(actual_path, expected_path);
 
         Ok(())
     }
     #[test]
-    #[ignore]
-    fn test_download() -> anyhow::Result<()> {
-        download_bible(&Config::default())?;
+    fn legacy_installs_are_moved() -> anyhow::Result<()> {
+        let registry = registry();
+        let net = registry.get("net")?;
+        let data_dir = tempfile::tempdir()?;
+        let legacy_dir = data_dir.path().join("English").join("Net");
+        fs::create_dir_all(&legacy_dir)?;
+        fs::write(legacy_dir.join("engnet_usfx.xml"), "<usfx></usfx>")?;
 
-        let data_dir: PathBuf = get_data_dir().context("couldn't determine data dir path")?;
-        let extracted_file: PathBuf = data_dir.join("English").join("Net").join("engnet_usfx.xml");
+        move_legacy_installs_in(data_dir.path(), &registry)?;
+        let path = data_dir
+            .path()
+            .join("English")
+            .join("net")
+            .join("engnet_usfx.xml");
+        assert!(path.is_file());
+        assert_eq!(path_in(data_dir.path(), net, &registry), path);
 
-        assert!(extracted_file.is_file());
+        //and after that there's nothing left to do
+        move_legacy_installs_in(data_dir.path(), &registry)?;
+        assert!(path.is_file());
         Ok(())
     }
 
     #[test]
-    fn test_get_zip() {
-        let url: String = get_bible_url(&Config::default());
-        let text: Bytes = get_bible_zip(&url).unwrap();
-        assert_ne!("", text);
+    fn local_bible_path() -> anyhow::Result<()> {
+        let registry = Registry::from_manifests(&[MYVERSION])?;
+        assert_eq!(
+            get_path_to_bible_file(registry.get("myversion")?, &registry)?,
+            PathBuf::from("/kyro/English/myversion/myversion.xml")
+        );
+        Ok(())
     }
+
     #[test]
-    fn test_usfx_filename() -> anyhow::Result<()> {
-        let mut fname = usfx_file(&Config::default(), XML)?;
-        assert_eq!(fname, String::from("engnet_usfx.xml"));
-        fname = usfx_file(&Config::default(), ZIP)?;
-        assert_eq!(fname, String::from("engnet_usfx.zip"));
+    fn missing_bibles_are_not_downloaded() -> anyhow::Result<()> {
+        let registry = Registry::from_manifests(&[r#"
+[[translation]]
+id = "missing"
+language = "eng"
+name = "Missing"
+format = "usfx"
+source = "file:///kyro/missing.zip"
+"#])?;
+        let missing = registry.get("missing")?;
+        let mut config = Config::default();
+        let dir = tempfile::tempdir()?;
+        let data_dir = dir.path().join("kyro");
+
+        let err = require_bible_in(&data_dir, &config, missing, &registry).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("run `kyro bibles install missing`"));
+
+        assert!(!data_dir.exists());
+
+        config.auto_download = true;
+        let err = require_bible_in(&data_dir, &config, missing, &registry).unwrap_err();
+        assert!(format!("{:#}", err).contains("cannot read /kyro/missing.zip"));
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn test_download() -> anyhow::Result<()> {
+        let registry = Registry::load()?;
+        let fetcher = Fetchers::new(&Config::default())?;
+        save_to_pc(registry.get("net")?, &registry, &fetcher)?;
+
+        let data_dir: PathBuf = get_data_dir().context("couldn't determine data dir path")?;
+        let extracted_file: PathBuf = data_dir.join("English").join("net").join("engnet_usfx.xml");
+
+        assert!(extracted_file.is_file());
         Ok(())
     }
+
+    mod downloads {
+        use super::*;
+        use crate::test_server::{etag, zip_of, Behaviour, Request, TestServer};
+        use sha2::{Digest, Sha256};
+
+        const SAMPLE: &str = include_str!("../tests/fixtures/sample.osis.xml");
+
+        fn fetcher() -> Fetchers {
+            Fetchers::new(&Config::default()).unwrap()
+        }
+
+        fn sample(server: &TestServer, checksum: Option<String>) -> Translation {
+            Translation {
+                id: "sample".to_string(),
+                language: "eng".to_string(),
+                name: "Sample Bible".to_string(),
+                format: "osis".to_string(),
+                source: server.url_of("/sample.zip"),
+                checksum,
+            }
+        }
+
+        fn start(behaviour: Behaviour) -> (TestServer, Vec<u8>) {
+            let zip = zip_of("sample.osis.xml", SAMPLE);
+            let server = TestServer::start(&[("/sample.zip", zip.clone())], behaviour);
+            (server, zip)
+        }
+
+        ///nothing but the finished Bible is left in the data dir
+        fn leftovers(data_dir: &Path) -> Vec<String> {
+            fs::read_dir(data_dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with('.'))
+                .collect()
+        }
+
+        #[test]
+        fn verified_download() -> anyhow::Result<()> {
+            let data_dir = tempfile::tempdir()?;
+            let (server, zip) = start(Behaviour::default());
+            let sum = format!("{:x}", Sha256::digest(&zip));
+            let registry = Registry::default();
+            let translation = sample(&server, Some(sum.to_uppercase()));
+
+            save_into(data_dir.path(), &translation, &registry, &fetcher())?;
+            let version_dir = data_dir.path().join("eng/sample");
+            assert!(version_dir.join("sample.osis.xml").is_file());
+            assert!(version_dir.join("sample.zip").is_file());
+            assert_eq!(fs::read_to_string(version_dir.join(CHECKSUM_FILE))?, sum);
+            assert!(leftovers(data_dir.path()).is_empty());
+
+            //it's only downloaded again when it has changed
+            assert!(!update_into(
+                data_dir.path(),
+                &translation,
+                &registry,
+                &fetcher()
+            )?);
+            assert_eq!(server.requests().len(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn corrupt_downloads_are_thrown_away() -> anyhow::Result<()> {
+            let data_dir = tempfile::tempdir()?;
+            let (server, _) = start(Behaviour::default());
+            let translation = sample(&server, Some("0".repeat(64)));
+
+            let err = save_into(
+                data_dir.path(),
+                &translation,
+                &Registry::default(),
+                &fetcher(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("corrupt"));
+            assert!(!data_dir.path().join("eng").exists());
+            assert!(leftovers(data_dir.path()).is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn interrupted_downloads_resume() -> anyhow::Result<()> {
+            let data_dir = tempfile::tempdir()?;
+            let (server, zip) = start(Behaviour {
+                cut_off_after: Some(100),
+                ..Default::default()
+            });
+            let registry = Registry::default();
+            let translation = sample(&server, Some(format!("{:x}", Sha256::digest(&zip))));
+
+            //without trying again so it's left part way through
+            let mut config = Config::default();
+            config.download.retries = 0;
+            let once = Fetchers::new(&config)?;
+            assert!(save_into(data_dir.path(), &translation, &registry, &once).is_err());
+            let part = part_path(data_dir.path(), &translation);
+            assert_eq!(fs::metadata(&part)?.len(), 100);
+            assert!(format::find_source(&data_dir.path().join("eng/sample")).is_none());
+
+            save_into(data_dir.path(), &translation, &registry, &fetcher())?;
+            assert!(!part.exists());
+            assert!(
+                format::read_bible(&data_dir.path().join("eng/sample/sample.osis.xml")).is_ok()
+            );
+            assert_eq!(
+                server.requests()[1],
+                Request {
+                    path: "/sample.zip".to_string(),
+                    range: Some("bytes=100-".to_string()),
+                    if_range: Some(etag(&zip)),
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn a_broken_install_is_replaced_whole() -> anyhow::Result<()> {
+            let data_dir = tempfile::tempdir()?;
+            let (server, _) = start(Behaviour::default());
+            let registry = Registry::default();
+            let version_dir = data_dir.path().join("eng/sample");
+            fs::create_dir_all(&version_dir)?;
+            fs::write(version_dir.join("stray.txt"), "left over")?;
+
+            save_into(
+                data_dir.path(),
+                &sample(&server, None),
+                &registry,
+                &fetcher(),
+            )?;
+            assert!(!version_dir.join("stray.txt").exists());
+            assert!(version_dir.join("sample.osis.xml").is_file());
+            assert!(leftovers(data_dir.path()).is_empty());
+            assert!(leftovers(&data_dir.path().join("eng")).is_empty());
+            Ok(())
+        }
+    }
 }
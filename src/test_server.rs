@@ -0,0 +1,180 @@
+//! A little HTTP server on localhost standing in for ebible.org in the tests
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+///Serves some files, remembering the requests it gets
+pub struct TestServer {
+    ///the address to put in front of a file's path (ie. http://127.0.0.1:4000)
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+///What was asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub path: String,
+    ///the value of the Range header (ie. bytes=100-)
+    pub range: Option<String>,
+    ///the value of the If-Range header, the range is only sent when it's the file's ETag
+    pub if_range: Option<String>,
+}
+
+///How the server behaves
+#[derive(Debug, Default, Clone)]
+pub struct Behaviour {
+    ///hang up on the first response after sending this many bytes of the body
+    pub cut_off_after: Option<usize>,
+    ///don't answer the first request for a few seconds
+    pub stall_first: bool,
+}
+
+impl TestServer {
+    pub fn start(files: &[(&str, Vec<u8>)], behaviour: Behaviour) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let files: HashMap<String, Vec<u8>> = files
+            .iter()
+            .map(|(path, body)| (path.to_string(), body.clone()))
+            .collect();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let seen = Arc::clone(&requests);
+        thread::spawn(move || {
+            let mut cut_off_after = behaviour.cut_off_after;
+            let mut stall = behaviour.stall_first;
+            for stream in listener.incoming().flatten() {
+                if stall {
+                    stall = false;
+                    thread::spawn(move || {
+                        thread::sleep(Duration::from_secs(3));
+                        drop(stream);
+                    });
+                    continue;
+                }
+                let request = match read_request(&stream) {
+                    Some(request) => request,
+                    None => continue,
+                };
+                seen.lock().unwrap().push(request.clone());
+                let _ = respond(
+                    stream,
+                    &request,
+                    files.get(&request.path),
+                    cut_off_after.take(),
+                );
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    ///the URL of a file on the server
+    pub fn url_of(&self, path: &str) -> String {
+        format!("{}{}", self.url, path)
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let path = line.split_whitespace().nth(1)?.to_string();
+
+    let (mut range, mut if_range) = (None, None);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).ok()? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("range") {
+                range = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("if-range") {
+                if_range = Some(value.trim().to_string());
+            }
+        }
+    }
+    Some(Request {
+        path,
+        range,
+        if_range,
+    })
+}
+
+fn respond(
+    mut stream: TcpStream,
+    request: &Request,
+    file: Option<&Vec<u8>>,
+    cut_off_after: Option<usize>,
+) -> std::io::Result<()> {
+    let body = match file {
+        Some(body) => body,
+        None => {
+            return stream.write_all(
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            )
+        }
+    };
+    let tag = etag(body);
+    //a range of something that has changed since is ignored and the whole file sent instead
+    let start = request
+        .range
+        .as_deref()
+        .filter(|_| request.if_range.as_ref().is_none_or(|v| *v == tag))
+        .and_then(|r| r.strip_prefix("bytes="))
+        .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok());
+
+    let (status, extra, part) = match start {
+        Some(start) if start >= body.len() => {
+            let header = format!("Content-Range: bytes */{}\r\n", body.len());
+            ("416 Range Not Satisfiable", header, &body[..0])
+        }
+        Some(start) => {
+            let header = format!(
+                "Content-Range: bytes {}-{}/{}\r\n",
+                start,
+                body.len() - 1,
+                body.len()
+            );
+            ("206 Partial Content", header, &body[start..])
+        }
+        None => ("200 OK", String::new(), &body[..]),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nETag: {}\r\n{}Connection: close\r\n\r\n",
+        status,
+        part.len(),
+        tag,
+        extra
+    )?;
+    match cut_off_after {
+        Some(n) => stream.write_all(&part[..n.min(part.len())]),
+        None => stream.write_all(part),
+    }
+}
+
+///the ETag the server gives a file, it changes whenever the file does
+pub fn etag(body: &[u8]) -> String {
+    let hash = body
+        .iter()
+        .fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(u64::from(*b)));
+    format!("\"{:x}\"", hash)
+}
+
+///a zip with one file in it
+pub fn zip_of(name: &str, contents: &str) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file(name, Default::default()).unwrap();
+    zip.write_all(contents.as_bytes()).unwrap();
+    zip.finish().unwrap().into_inner()
+}